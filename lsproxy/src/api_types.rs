@@ -1,9 +1,14 @@
 use log::warn;
-use lsp_types::{GotoDefinitionResponse, Location, LocationLink};
+use lsp_types::{
+    DocumentSymbol, DocumentSymbolResponse, GotoDefinitionResponse, Location, LocationLink,
+    SymbolKind,
+};
 use serde::{Deserialize, Serialize};
 use serde_json::{to_value, Value};
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::hash::Hash;
+use std::iter::once;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, LazyLock, RwLock};
 use strum_macros::{Display, EnumString};
@@ -14,6 +19,10 @@ use crate::utils::file_utils::uri_to_relative_path_string;
 static GLOBAL_MOUNT_DIR: LazyLock<Arc<RwLock<PathBuf>>> =
     LazyLock::new(|| Arc::new(RwLock::new(PathBuf::from("/mnt/workspace"))));
 
+/// Import roots of the Python code relative to the mount dir, e.g. `src`.
+static PYTHON_SOURCE_ROOTS: LazyLock<RwLock<Vec<PathBuf>>> =
+    LazyLock::new(|| RwLock::new(Vec::new()));
+
 thread_local! {
     static THREAD_LOCAL_MOUNT_DIR: RefCell<Option<PathBuf>> = RefCell::new(None);
 }
@@ -44,6 +53,11 @@ pub fn set_global_mount_dir(path: impl AsRef<Path>) {
     *global_dir = path.as_ref().to_path_buf();
 }
 
+/// Sets the import roots stripped from the paths of Python modules in qualified names.
+pub fn set_python_source_roots(roots: Vec<PathBuf>) {
+    *PYTHON_SOURCE_ROOTS.write().unwrap() = roots;
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     pub error: String,
@@ -65,6 +79,18 @@ pub enum SupportedLanguages {
     CPP,
     #[serde(rename = "java")]
     Java,
+    #[serde(rename = "bash")]
+    Bash,
+    #[serde(rename = "perl")]
+    Perl,
+    #[serde(rename = "csharp")]
+    CSharp,
+    #[serde(rename = "ruby")]
+    Ruby,
+    #[serde(rename = "php")]
+    PHP,
+    #[serde(rename = "svelte")]
+    Svelte,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
@@ -83,6 +109,10 @@ pub struct FilePosition {
     #[schema(example = "src/main.py")]
     pub path: String,
     pub position: Position,
+    /// Index of the Jupyter notebook cell the position is in, counting all cells.
+    /// When set, `position.line` is relative to the start of the cell.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cell: Option<u32>,
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
@@ -94,6 +124,10 @@ pub struct FileRange {
     pub start: Position,
     /// The end position of the range.
     pub end: Position,
+    /// Index of the Jupyter notebook cell the range starts in, counting all cells.
+    /// When set, `start.line` and `end.line` are relative to the start of the cell.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cell: Option<u32>,
 }
 
 impl From<Position> for lsp_types::Position {
@@ -138,6 +172,23 @@ pub struct Symbol {
     #[schema(example = "class")]
     pub kind: String,
 
+    /// The name of the symbol this one is defined in, e.g. the class of a method.
+    pub container_name: Option<String>,
+
+    /// The name of the symbol qualified by its containers, and by its module in languages where
+    /// modules follow the file path, e.g. `models.User.save` or `crate::node::Node::new`.
+    #[schema(example = "models.User")]
+    pub qualified_name: String,
+
+    /// The decorators of the definition without the `@`, e.g. `property` for a Python property.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub decorators: Vec<String>,
+
+    /// The docstring or leading doc comment of the definition, without quotes or comment markers.
+    /// Only set when requested with `include_documentation`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<String>,
+
     /// The start position of the symbol's identifier.
     pub identifier_position: FilePosition,
 
@@ -186,21 +237,90 @@ pub struct FileSymbolsRequest {
     /// The path to the file to get the symbols for, relative to the root of the workspace.
     #[schema(example = "src/main.py")]
     pub file_path: String,
+
+    /// Whether to include the docstring or leading doc comment of each symbol.
+    /// Defaults to false.
+    #[serde(default)]
+    #[schema(example = false)]
+    pub include_documentation: bool,
 }
 
-/// Request to get the symbols in the workspace.
-#[allow(unused)] // TODO re-implement using textDocument/symbol
+/// Request to search the workspace for code matching an ast-grep pattern or rule.
+///
+/// Exactly one of `pattern` and `rule` has to be set.
+#[derive(Deserialize, ToSchema)]
+pub struct StructuralSearchRequest {
+    /// An [ast-grep pattern](https://ast-grep.github.io/guide/pattern-syntax.html). `$NAME`
+    /// matches a single node and `$$$ARGS` any number of nodes.
+    #[schema(example = "requests.get($URL)")]
+    pub pattern: Option<String>,
+    /// An [ast-grep rule](https://ast-grep.github.io/reference/rule.html) in YAML, either the
+    /// rule object or a whole rule file with `constraints` and `utils`.
+    #[schema(
+        example = "pattern: requests.get($$$ARGS)\nnot:\n  has:\n    kind: keyword_argument\n    regex: ^timeout=\n    stopBy: end"
+    )]
+    pub rule: Option<String>,
+    /// The language of the pattern, e.g. `python`, `tsx` or `rust`.
+    #[schema(example = "python")]
+    pub language: String,
+    /// Only search the files matching this glob, relative to the root of the workspace.
+    #[schema(example = "src/**/*.py")]
+    pub path_glob: Option<String>,
+}
+
+/// Request to rewrite the code matching an ast-grep pattern or rule.
+///
+/// Exactly one of `pattern` and `rule` has to be set.
+#[derive(Deserialize, ToSchema)]
+pub struct RewriteRequest {
+    /// An [ast-grep pattern](https://ast-grep.github.io/guide/pattern-syntax.html) of the code
+    /// to replace.
+    #[schema(example = "requests.get($URL)")]
+    pub pattern: Option<String>,
+    /// An [ast-grep rule](https://ast-grep.github.io/reference/rule.html) in YAML, either the
+    /// rule object or a whole rule file with `constraints` and `utils`.
+    pub rule: Option<String>,
+    /// The replacement of each match, where `$NAME` and `$$$NAME` stand for the code captured by
+    /// the meta-variables of the pattern.
+    #[schema(example = "requests.get($URL, timeout=10)")]
+    pub fix: String,
+    /// The language of the pattern, e.g. `python`, `tsx` or `rust`.
+    #[schema(example = "python")]
+    pub language: String,
+    /// Only rewrite the files matching this glob, relative to the root of the workspace.
+    #[schema(example = "src/**/*.py")]
+    pub path_glob: Option<String>,
+    /// Write the changes to the files, instead of only returning their diffs.
+    /// Defaults to false.
+    #[serde(default)]
+    #[schema(example = false)]
+    pub apply: bool,
+}
+
+/// Request to get the compile command of a C or C++ file.
+#[derive(Deserialize, ToSchema, IntoParams)]
+pub struct CompileCommandRequest {
+    /// The path to the file, relative to the root of the workspace.
+    #[schema(example = "src/main.c")]
+    pub file_path: String,
+}
+
+/// Request to search the symbols of the workspace.
 #[derive(Deserialize, ToSchema, IntoParams)]
 pub struct WorkspaceSymbolsRequest {
-    /// The query to search for.
-    #[schema(example = "User")]
-    pub query: String,
+    /// Only return the symbols whose name contains this text, ignoring case.
+    #[schema(example = "user")]
+    pub query: Option<String>,
 
-    /// Whether to include the raw response from the langserver in the response.
+    /// Only return the symbols of this kind, e.g. `function` or `class`.
+    #[schema(example = "class")]
+    pub kind: Option<String>,
+
+    /// Whether to include the docstring or leading doc comment of each symbol.
     /// Defaults to false.
     #[serde(default)]
     #[schema(example = false)]
-    pub include_raw_response: bool,
+    pub include_documentation: bool,
 }
 
 /// Response to a definition request.
@@ -265,6 +385,102 @@ pub struct ReferencesResponse {
     pub context: Option<Vec<CodeContext>>,
 }
 
+/// The compile command clangd uses for a file.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CompileCommandResponse {
+    /// The working directory of the command.
+    #[schema(example = "/mnt/workspace")]
+    pub directory: String,
+    #[schema(
+        example = "/usr/bin/cc -I/mnt/workspace/include -std=c11 -c /mnt/workspace/src/main.c"
+    )]
+    pub command: String,
+    /// Where the command comes from: `compile_commands.json` or `compile_flags.txt` of the
+    /// workspace, or `generated` by lsproxy from the CMake and Makefiles.
+    #[schema(example = "generated")]
+    pub source: String,
+}
+
+/// Code matching a structural search.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StructuralMatch {
+    pub range: FileRange,
+    /// The source code of the match.
+    #[schema(example = "requests.get(url)")]
+    pub text: String,
+    /// The code captured by each meta-variable of the pattern, by name without the `$`.
+    /// A `$$$` meta-variable spans all the nodes it matched.
+    pub meta_variables: BTreeMap<String, FileRange>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StructuralSearchResponse {
+    pub matches: Vec<StructuralMatch>,
+}
+
+/// The changes of a rewrite to a file.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FileRewrite {
+    #[schema(example = "src/client.py")]
+    pub path: String,
+    /// The number of matches replaced in the file.
+    #[schema(example = 1)]
+    pub matches: usize,
+    /// The changes as a unified diff.
+    #[schema(
+        example = "--- a/src/client.py\n+++ b/src/client.py\n@@ -2,2 +2,2 @@\n def fetch(url):\n-    return requests.get(url)\n+    return requests.get(url, timeout=10)\n"
+    )]
+    pub diff: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RewriteResponse {
+    pub files: Vec<FileRewrite>,
+    /// Whether the changes were written to the files.
+    pub applied: bool,
+    /// Files of the rule's language left alone because they aren't valid UTF-8.
+    pub skipped: Vec<String>,
+}
+
+/// Status of lsproxy and its language servers.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HealthResponse {
+    /// `ok` once every language server is ready, `indexing` otherwise.
+    #[schema(example = "ok")]
+    pub status: String,
+    pub language_servers: Vec<LanguageServerHealth>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LanguageServerHealth {
+    /// The language, or the name of a language server from the configuration file.
+    #[schema(example = "python")]
+    pub language: String,
+    /// The name the server reported when initializing.
+    #[schema(example = "jedi-language-server")]
+    pub name: Option<String>,
+    #[schema(example = "0.41.4")]
+    pub version: Option<String>,
+    #[schema(example = 42)]
+    pub pid: Option<u32>,
+    #[schema(example = 3600)]
+    pub uptime_secs: u64,
+    /// Whether the server is done indexing the workspace.
+    #[schema(example = true)]
+    pub ready: bool,
+    /// What the server is busy with, e.g. `Indexing`.
+    pub pending_work: Vec<String>,
+    /// Requests sent to the server and waiting for a response.
+    #[schema(example = 0)]
+    pub in_flight_requests: usize,
+    /// The latest error answered by the server.
+    pub last_error: Option<String>,
+    /// The capabilities negotiated with `initialize`.
+    ///
+    /// https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#serverCapabilities
+    pub capabilities: Value,
+}
+
 pub type SymbolResponse = Vec<Symbol>;
 
 impl From<(GotoDefinitionResponse, Option<Vec<CodeContext>>, bool)> for DefinitionResponse {
@@ -330,6 +546,7 @@ impl From<Location> for FilePosition {
                 line: location.range.start.line,
                 character: location.range.start.character,
             },
+            cell: None,
         }
     }
 }
@@ -342,6 +559,283 @@ impl From<LocationLink> for FilePosition {
                 line: link.target_range.start.line,
                 character: link.target_range.start.character,
             },
+            cell: None,
+        }
+    }
+}
+
+/// Qualifies `name` with `containers`, outermost first, and with the module of `path` in Python
+/// and Rust, where it follows the file path.
+pub(crate) fn qualified_name(path: &str, containers: &[String], name: &str) -> String {
+    let path = Path::new(path);
+    let (module, separator) = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("py" | "pyi" | "ipynb") => (
+            python_module(path, &PYTHON_SOURCE_ROOTS.read().unwrap()),
+            ".",
+        ),
+        Some("rs") => (Some(rust_module(path)), "::"),
+        Some("c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" | "rb" | "php") => {
+            (None, "::")
+        }
+        _ => (None, "."),
+    };
+    module
+        .into_iter()
+        .chain(containers.iter().cloned())
+        .chain(once(name.to_string()))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// `pkg/models.py` is `pkg.models`, `pkg/__init__.py` is `pkg`. Paths are taken from the
+/// innermost of the `source_roots` containing them, e.g. `src/pkg/models.py` is `pkg.models`
+/// with the source root `src`.
+fn python_module(path: &Path, source_roots: &[PathBuf]) -> Option<String> {
+    let path = source_roots
+        .iter()
+        .filter_map(|root| path.strip_prefix(root).ok())
+        .min_by_key(|relative| relative.components().count())
+        .unwrap_or(path);
+    let mut parts: Vec<String> = path
+        .with_extension("")
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    if parts.last().is_some_and(|part| part == "__init__") {
+        parts.pop();
+    }
+    (!parts.is_empty()).then(|| parts.join("."))
+}
+
+/// `src/node.rs` is `crate::node`, `src/graph/mod.rs` is `crate::graph` and `src/lib.rs` is
+/// `crate`. Paths are taken from the last `src` directory, if any.
+fn rust_module(path: &Path) -> String {
+    let parts: Vec<String> = path
+        .with_extension("")
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    let mut parts = match parts.iter().rposition(|part| part == "src") {
+        Some(src) => parts[src + 1..].to_vec(),
+        None => parts,
+    };
+    if parts.last().is_some_and(|part| part == "mod")
+        || (parts.len() == 1 && (parts[0] == "lib" || parts[0] == "main"))
+    {
+        parts.pop();
+    }
+    once(String::from("crate"))
+        .chain(parts)
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+/// The symbols a language server reported for `path`, depth first. Containers come from the
+/// nesting of `DocumentSymbol`s, or from the `containerName` of flat `SymbolInformation`s.
+pub fn symbols_from_lsp(path: &str, response: DocumentSymbolResponse) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    match response {
+        DocumentSymbolResponse::Nested(document_symbols) => {
+            for document_symbol in document_symbols {
+                push_document_symbol(path, document_symbol, &mut Vec::new(), &mut symbols);
+            }
+        }
+        DocumentSymbolResponse::Flat(symbol_informations) => {
+            for info in symbol_informations {
+                let containers: Vec<String> = info.container_name.into_iter().collect();
+                symbols.push(Symbol {
+                    qualified_name: qualified_name(path, &containers, &info.name),
+                    name: info.name,
+                    kind: symbol_kind_name(info.kind),
+                    container_name: containers.last().cloned(),
+                    decorators: Vec::new(),
+                    documentation: None,
+                    identifier_position: FilePosition {
+                        path: path.to_string(),
+                        position: info.location.range.start.into(),
+                        cell: None,
+                    },
+                    range: FileRange {
+                        path: path.to_string(),
+                        start: info.location.range.start.into(),
+                        end: info.location.range.end.into(),
+                        cell: None,
+                    },
+                });
+            }
         }
     }
+    symbols
+}
+
+fn push_document_symbol(
+    path: &str,
+    document_symbol: DocumentSymbol,
+    containers: &mut Vec<String>,
+    symbols: &mut Vec<Symbol>,
+) {
+    symbols.push(Symbol {
+        name: document_symbol.name.clone(),
+        kind: symbol_kind_name(document_symbol.kind),
+        container_name: containers.last().cloned(),
+        qualified_name: qualified_name(path, containers, &document_symbol.name),
+        decorators: Vec::new(),
+        documentation: None,
+        identifier_position: FilePosition {
+            path: path.to_string(),
+            position: document_symbol.selection_range.start.into(),
+            cell: None,
+        },
+        range: FileRange {
+            path: path.to_string(),
+            start: document_symbol.range.start.into(),
+            end: document_symbol.range.end.into(),
+            cell: None,
+        },
+    });
+    containers.push(document_symbol.name);
+    for child in document_symbol.children.into_iter().flatten() {
+        push_document_symbol(path, child, containers, symbols);
+    }
+    containers.pop();
+}
+
+/// Names the kinds like the ids of the ast-grep rules, e.g. `function` or `enum-member`.
+fn symbol_kind_name(kind: SymbolKind) -> String {
+    let name = match kind {
+        SymbolKind::FILE => "file",
+        SymbolKind::MODULE => "module",
+        SymbolKind::NAMESPACE => "namespace",
+        SymbolKind::PACKAGE => "package",
+        SymbolKind::CLASS => "class",
+        SymbolKind::METHOD => "method",
+        SymbolKind::PROPERTY => "property",
+        SymbolKind::FIELD => "field",
+        SymbolKind::CONSTRUCTOR => "constructor",
+        SymbolKind::ENUM => "enum",
+        SymbolKind::INTERFACE => "interface",
+        SymbolKind::FUNCTION => "function",
+        SymbolKind::VARIABLE => "variable",
+        SymbolKind::CONSTANT => "constant",
+        SymbolKind::STRUCT => "struct",
+        SymbolKind::ENUM_MEMBER => "enum-member",
+        SymbolKind::EVENT => "event",
+        SymbolKind::OPERATOR => "operator",
+        SymbolKind::TYPE_PARAMETER => "type-parameter",
+        _ => "symbol",
+    };
+    name.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_qualified_name() {
+        let containers = vec![String::from("User")];
+        assert_eq!(
+            qualified_name("app/models.py", &containers, "save"),
+            "app.models.User.save"
+        );
+        assert_eq!(qualified_name("app/__init__.py", &[], "main"), "app.main");
+        assert_eq!(
+            qualified_name("src/node.rs", &[String::from("Node")], "new"),
+            "crate::node::Node::new"
+        );
+        assert_eq!(
+            qualified_name("src/graph/mod.rs", &[], "Graph"),
+            "crate::graph::Graph"
+        );
+        assert_eq!(qualified_name("src/lib.rs", &[], "run"), "crate::run");
+        assert_eq!(
+            qualified_name("src/astar.cpp", &[String::from("aStar")], "search"),
+            "aStar::search"
+        );
+        assert_eq!(
+            qualified_name("src/Node.java", &containers, "save"),
+            "User.save"
+        );
+    }
+
+    #[test]
+    fn test_python_module_source_roots() {
+        let roots = vec![PathBuf::from("src"), PathBuf::from("src/vendor")];
+        assert_eq!(
+            python_module(Path::new("src/pkg/models.py"), &roots).as_deref(),
+            Some("pkg.models")
+        );
+        assert_eq!(
+            python_module(Path::new("src/vendor/lib/__init__.py"), &roots).as_deref(),
+            Some("lib")
+        );
+        assert_eq!(
+            python_module(Path::new("tests/test_models.py"), &roots).as_deref(),
+            Some("tests.test_models")
+        );
+        assert_eq!(
+            python_module(Path::new("src/pkg/models.py"), &[]).as_deref(),
+            Some("src.pkg.models")
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_symbols_from_nested_document_symbols() {
+        let range = |start: u32, end: u32| lsp_types::Range {
+            start: lsp_types::Position::new(start, 0),
+            end: lsp_types::Position::new(end, 0),
+        };
+        let document_symbol = |name: &str, kind, lines: (u32, u32), children| DocumentSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind,
+            tags: None,
+            deprecated: None,
+            range: range(lines.0, lines.1),
+            selection_range: range(lines.0, lines.0),
+            children,
+        };
+        let response = DocumentSymbolResponse::Nested(vec![document_symbol(
+            "User",
+            SymbolKind::CLASS,
+            (0, 5),
+            Some(vec![document_symbol(
+                "save",
+                SymbolKind::METHOD,
+                (2, 4),
+                None,
+            )]),
+        )]);
+
+        let symbols: Vec<(String, String, Option<String>, String)> =
+            symbols_from_lsp("models.py", response)
+                .into_iter()
+                .map(|symbol| {
+                    (
+                        symbol.name,
+                        symbol.kind,
+                        symbol.container_name,
+                        symbol.qualified_name,
+                    )
+                })
+                .collect();
+        assert_eq!(
+            symbols,
+            vec![
+                (
+                    String::from("User"),
+                    String::from("class"),
+                    None,
+                    String::from("models.User")
+                ),
+                (
+                    String::from("save"),
+                    String::from("method"),
+                    Some(String::from("User")),
+                    String::from("models.User.save")
+                ),
+            ]
+        );
+    }
 }
@@ -1,35 +1,1099 @@
-use tokio::process::Command;
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
-use super::types::AstGrepMatch;
+use ast_grep_config::{from_yaml_string, GlobalRules, RuleConfig, SerializableRuleConfig};
+use ast_grep_core::matcher::MatcherExt;
+use ast_grep_core::meta_var::MetaVariable as MetaVariableKind;
+use ast_grep_language::SupportLang;
+use log::debug;
+use serde::Deserialize;
+use serde_yaml::{Mapping, Value};
 
+use super::document::{Document, SgNode, SgNodeMatch};
+use super::documentation::documentation;
+use super::language::{language_from_extension, language_from_name, SgLanguage};
+use super::types::{
+    AstGrepMatch, FileEdit, MetaVariable, MetaVariables, MultiVariables, RuleMatch, SingleVariable,
+};
+use crate::config::LsproxyConfig;
+use crate::utils::language_detection::{ast_grep_language_globs, sniffed_extension};
+use crate::utils::notebook::{is_notebook, NotebookDocument};
+
+/// The built-in `sgconfig.yml`, its `ruleDirs` are compiled in as `BUILTIN_RULES`.
+const BUILTIN_CONFIG: &str = include_str!("sgconfig.yml");
+
+/// The files of `rules/`, new rule files have to be added here.
+const BUILTIN_RULES: &[(&str, &str)] = &[
+    ("bash/function.yml", include_str!("rules/bash/function.yml")),
+    ("bash/variable.yml", include_str!("rules/bash/variable.yml")),
+    ("cpp/class.yml", include_str!("rules/cpp/class.yml")),
+    ("cpp/enum.yml", include_str!("rules/cpp/enum.yml")),
+    (
+        "cpp/function-declaration.yml",
+        include_str!("rules/cpp/function-declaration.yml"),
+    ),
+    (
+        "cpp/function-definition.yml",
+        include_str!("rules/cpp/function-definition.yml"),
+    ),
+    ("cpp/macro.yml", include_str!("rules/cpp/macro.yml")),
+    ("cpp/namespace.yml", include_str!("rules/cpp/namespace.yml")),
+    ("cpp/type.yml", include_str!("rules/cpp/type.yml")),
+    ("csharp/class.yml", include_str!("rules/csharp/class.yml")),
+    (
+        "csharp/interface.yml",
+        include_str!("rules/csharp/interface.yml"),
+    ),
+    ("csharp/method.yml", include_str!("rules/csharp/method.yml")),
+    (
+        "csharp/property.yml",
+        include_str!("rules/csharp/property.yml"),
+    ),
+    ("go/constant.yml", include_str!("rules/go/constant.yml")),
+    ("go/function.yml", include_str!("rules/go/function.yml")),
+    ("go/method.yml", include_str!("rules/go/method.yml")),
+    ("go/type.yml", include_str!("rules/go/type.yml")),
+    ("go/variable.yml", include_str!("rules/go/variable.yml")),
+    ("java/class.yml", include_str!("rules/java/class.yml")),
+    ("java/enum.yml", include_str!("rules/java/enum.yml")),
+    ("java/field.yml", include_str!("rules/java/field.yml")),
+    (
+        "java/interface.yml",
+        include_str!("rules/java/interface.yml"),
+    ),
+    ("java/method.yml", include_str!("rules/java/method.yml")),
+    ("java/record.yml", include_str!("rules/java/record.yml")),
+    (
+        "javascript/class.yml",
+        include_str!("rules/javascript/class.yml"),
+    ),
+    (
+        "javascript/function.yml",
+        include_str!("rules/javascript/function.yml"),
+    ),
+    (
+        "javascript/method.yml",
+        include_str!("rules/javascript/method.yml"),
+    ),
+    (
+        "javascript/variable.yml",
+        include_str!("rules/javascript/variable.yml"),
+    ),
+    ("perl/constant.yml", include_str!("rules/perl/constant.yml")),
+    ("perl/function.yml", include_str!("rules/perl/function.yml")),
+    ("perl/module.yml", include_str!("rules/perl/module.yml")),
+    ("php/class.yml", include_str!("rules/php/class.yml")),
+    ("php/function.yml", include_str!("rules/php/function.yml")),
+    ("php/interface.yml", include_str!("rules/php/interface.yml")),
+    ("php/method.yml", include_str!("rules/php/method.yml")),
+    ("php/trait.yml", include_str!("rules/php/trait.yml")),
+    ("python/class.yml", include_str!("rules/python/class.yml")),
+    (
+        "python/constant.yml",
+        include_str!("rules/python/constant.yml"),
+    ),
+    (
+        "python/function.yml",
+        include_str!("rules/python/function.yml"),
+    ),
+    (
+        "python/variable.yml",
+        include_str!("rules/python/variable.yml"),
+    ),
+    ("ruby/class.yml", include_str!("rules/ruby/class.yml")),
+    ("ruby/constant.yml", include_str!("rules/ruby/constant.yml")),
+    ("ruby/method.yml", include_str!("rules/ruby/method.yml")),
+    ("ruby/module.yml", include_str!("rules/ruby/module.yml")),
+    ("rust/constant.yml", include_str!("rules/rust/constant.yml")),
+    ("rust/enum.yml", include_str!("rules/rust/enum.yml")),
+    ("rust/function.yml", include_str!("rules/rust/function.yml")),
+    (
+        "rust/implementation.yml",
+        include_str!("rules/rust/implementation.yml"),
+    ),
+    ("rust/macro.yml", include_str!("rules/rust/macro.yml")),
+    ("rust/module.yml", include_str!("rules/rust/module.yml")),
+    ("rust/struct.yml", include_str!("rules/rust/struct.yml")),
+    ("rust/trait.yml", include_str!("rules/rust/trait.yml")),
+    ("rust/type.yml", include_str!("rules/rust/type.yml")),
+    ("rust/variable.yml", include_str!("rules/rust/variable.yml")),
+    ("tsx/class.yml", include_str!("rules/tsx/class.yml")),
+    ("tsx/enum.yml", include_str!("rules/tsx/enum.yml")),
+    ("tsx/function.yml", include_str!("rules/tsx/function.yml")),
+    ("tsx/interface.yml", include_str!("rules/tsx/interface.yml")),
+    ("tsx/method.yml", include_str!("rules/tsx/method.yml")),
+    ("tsx/type.yml", include_str!("rules/tsx/type.yml")),
+    ("tsx/variable.yml", include_str!("rules/tsx/variable.yml")),
+];
+
+/// Parsed documents kept in memory, keyed by language and content hash.
+const MAX_CACHED_DOCUMENTS: usize = 1024;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SgConfig {
+    #[serde(default)]
+    language_globs: HashMap<String, Vec<String>>,
+}
+
+/// Runs ast-grep rules in-process with `ast-grep-core`.
+///
+/// Rules are compiled once, parsed documents and their symbols are cached by content hash.
 pub struct AstGrepClient {
-    pub config_path: String,
+    rules: HashMap<SgLanguage, Vec<RuleConfig<SgLanguage>>>,
+    language_globs: Vec<(SgLanguage, glob::Pattern)>,
+    cache: Mutex<DocumentCache>,
+    fingerprint: u64,
+}
+
+#[derive(Default)]
+struct DocumentCache {
+    documents: HashMap<(SgLanguage, u64), Arc<Document>>,
+    order: VecDeque<(SgLanguage, u64)>,
 }
 
+/// A file that isn't rewritten because it isn't valid UTF-8.
+#[derive(Debug)]
+pub struct NotUtf8Error(pub String);
+
+impl fmt::Display for NotUtf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is not valid UTF-8", self.0)
+    }
+}
+
+impl Error for NotUtf8Error {}
+
 impl AstGrepClient {
+    /// Loads the built-in rules, then the rule files of `rule_dirs`. The `languageGlobs` of the
+    /// built-in config are extended with the configured extensions and the configured language
+    /// servers that name an `ast_grep_language`.
+    pub fn new(config: &LsproxyConfig, rule_dirs: &[PathBuf]) -> Result<Self, Box<dyn Error>> {
+        let sg_config: SgConfig = serde_yaml::from_str(BUILTIN_CONFIG)?;
+
+        // Globs are tried in order, configured extensions override the built-in globs.
+        let mut globs: Vec<(String, String)> = Vec::new();
+        for (language, patterns) in ast_grep_language_globs(&config.extensions) {
+            globs.extend(patterns.into_iter().map(|p| (language.clone(), p)));
+        }
+        for server in &config.language_servers {
+            if let Some(language) = &server.ast_grep_language {
+                globs.extend(
+                    server
+                        .extensions
+                        .iter()
+                        .map(|ext| (language.clone(), format!("*.{}", ext))),
+                );
+            }
+        }
+        let mut builtin_globs: Vec<_> = sg_config.language_globs.into_iter().collect();
+        builtin_globs.sort();
+        for (language, patterns) in builtin_globs {
+            globs.extend(patterns.into_iter().map(|p| (language.clone(), p)));
+        }
+        let mut language_globs = Vec::new();
+        for (language, pattern) in globs {
+            let sg_language = language_from_name(&language)
+                .ok_or_else(|| format!("Unknown ast-grep language '{}'", language))?;
+            language_globs.push((sg_language, glob::Pattern::new(&pattern)?));
+        }
+
+        let mut fingerprint = DefaultHasher::new();
+        BUILTIN_CONFIG.hash(&mut fingerprint);
+        for (language, glob) in &language_globs {
+            (language, glob.as_str()).hash(&mut fingerprint);
+        }
+        let mut rules: HashMap<SgLanguage, Vec<RuleConfig<SgLanguage>>> = HashMap::new();
+        for (path, content) in BUILTIN_RULES {
+            content.hash(&mut fingerprint);
+            for rule in parse_rules(content).map_err(|e| format!("{}: {}", path, e))? {
+                rules.entry(rule.language).or_default().push(rule);
+            }
+        }
+        for (path, content) in read_rule_dirs(rule_dirs)? {
+            (&path, &content).hash(&mut fingerprint);
+            let file_rules = parse_rules(&content)
+                .map_err(|e| format!("Invalid rule file {}: {}", path.display(), e))?;
+            debug!("Loaded {} rules from {}", file_rules.len(), path.display());
+            for rule in file_rules {
+                rules.entry(rule.language).or_default().push(rule);
+            }
+        }
+
+        Ok(Self {
+            rules,
+            language_globs,
+            cache: Mutex::new(DocumentCache::default()),
+            fingerprint: fingerprint.finish(),
+        })
+    }
+
+    /// Changes with the rules and the languages of files, symbols found with another
+    /// fingerprint may be outdated.
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// The language ast-grep parses `path` with, `languageGlobs` take precedence over extensions.
+    pub fn language_for_path(&self, path: &Path) -> Option<SgLanguage> {
+        self.language_globs
+            .iter()
+            .find(|(_, glob)| glob.matches_path(path))
+            .map(|(language, _)| *language)
+            .or_else(|| language_from_extension(path))
+    }
+
+    /// The language `path` is scanned in, like `language_for_path` with notebooks as Python
+    /// and scripts in the language of their shebang or modeline.
+    fn file_language(&self, path: &Path) -> Option<SgLanguage> {
+        if is_notebook(path) {
+            return Some(SgLanguage::Builtin(SupportLang::Python));
+        }
+        match sniffed_extension(path) {
+            Some(extension) => self.language_for_path(Path::new(&format!("script.{}", extension))),
+            None => self.language_for_path(path),
+        }
+    }
+
+    /// Whether there are symbol rules for the language of `file_name`.
+    pub fn has_symbol_rules(&self, file_name: &str) -> bool {
+        self.file_language(Path::new(file_name))
+            .is_some_and(|language| self.rules.contains_key(&language))
+    }
+
+    /// The symbols of `file_name`. Notebooks are scanned as their Python document, and files
+    /// without a known extension in the language of their shebang or modeline.
     pub async fn get_file_symbols(
         &self,
         file_name: &str,
     ) -> Result<Vec<AstGrepMatch>, Box<dyn std::error::Error>> {
-        let command_result = Command::new("sg")
-            .arg("scan")
-            .arg("--config")
-            .arg(&self.config_path)
-            .arg("--json")
-            .arg(file_name)
-            .output()
-            .await?;
+        let path = Path::new(file_name);
+        let Some(language) = self.file_language(path) else {
+            debug!("No ast-grep language for {}", file_name);
+            return Ok(Vec::new());
+        };
+        let document = if is_notebook(path) {
+            let content = tokio::fs::read_to_string(file_name).await?;
+            self.document(
+                language,
+                &NotebookDocument::parse(&content)
+                    .map_err(|e| e.to_string())?
+                    .text,
+            )?
+        } else {
+            self.read_document(language, file_name).await?
+        };
 
-        if !command_result.status.success() {
-            let error = String::from_utf8_lossy(&command_result.stderr);
-            return Err(format!("sg command failed: {}", error).into());
+        let mut symbols = document
+            .symbols
+            .get_or_init(|| self.scan_symbols(&document))
+            .clone();
+        for symbol in symbols.iter_mut() {
+            symbol.file = file_name.to_string();
         }
-
-        let output = String::from_utf8(command_result.stdout)?;
-
-        let mut symbols: Vec<AstGrepMatch> = serde_json::from_str(&output)
-            .map_err(|e| format!("Failed to parse JSON: {}\nJSON: {}", e, output))?;
         symbols.sort_by_key(|s| s.range.start.line);
         Ok(symbols)
     }
+
+    /// Matches `rule` against every node of `file_name` written in the rule's language, including
+    /// the scripts embedded in HTML files.
+    pub async fn scan_file(
+        &self,
+        rule: &RuleConfig<SgLanguage>,
+        file_name: &str,
+    ) -> Result<Vec<RuleMatch>, Box<dyn Error>> {
+        let Some(document) = self.rule_document(rule, file_name).await? else {
+            return Ok(Vec::new());
+        };
+
+        let mut matches = Vec::new();
+        for root in document.roots.iter().filter(|r| *r.lang() == rule.language) {
+            for node in root.root().dfs() {
+                if let Some(node_match) = rule.matcher.match_node(node) {
+                    matches.push(rule_match(&document, rule, file_name, &node_match));
+                }
+            }
+        }
+        Ok(matches)
+    }
+
+    /// Replaces the matches of `rule` in `file_name` with its `fix`, `None` if nothing matches.
+    /// Matches inside of an earlier match are left alone. Fails with `NotUtf8Error` when the
+    /// file isn't valid UTF-8.
+    pub async fn rewrite_file(
+        &self,
+        rule: &RuleConfig<SgLanguage>,
+        file_name: &str,
+    ) -> Result<Option<FileEdit>, Box<dyn Error>> {
+        let fixer = rule
+            .fixer
+            .first()
+            .ok_or_else(|| format!("Rule {} has no fix", rule.id))?;
+        let Some(language) = self.rule_language(rule, file_name) else {
+            return Ok(None);
+        };
+        // Decoding lossily would write replacement characters over the invalid bytes
+        let source = String::from_utf8(tokio::fs::read(file_name).await?)
+            .map_err(|_| NotUtf8Error(file_name.to_string()))?;
+        let document = self.document(language, &source)?;
+
+        let mut edits = Vec::new();
+        for root in document.roots.iter().filter(|r| *r.lang() == rule.language) {
+            for node in root.root().dfs() {
+                if let Some(node_match) = rule.matcher.match_node(node) {
+                    edits.push(node_match.make_edit(&rule.matcher, fixer));
+                }
+            }
+        }
+        edits.sort_by_key(|edit| edit.position);
+
+        let source = document.source();
+        let mut rewritten = String::new();
+        let mut end = 0;
+        let mut matches = 0;
+        for edit in edits {
+            if edit.position < end {
+                continue;
+            }
+            rewritten.push_str(&source[end..edit.position]);
+            rewritten.push_str(std::str::from_utf8(&edit.inserted_text)?);
+            end = edit.position + edit.deleted_length;
+            matches += 1;
+        }
+        if matches == 0 {
+            return Ok(None);
+        }
+        rewritten.push_str(&source[end..]);
+        Ok(Some(FileEdit {
+            file: file_name.to_string(),
+            source: source.to_string(),
+            rewritten,
+            matches,
+        }))
+    }
+
+    /// The document of `file_name` if it is written in the language of `rule` or embeds it.
+    async fn rule_document(
+        &self,
+        rule: &RuleConfig<SgLanguage>,
+        file_name: &str,
+    ) -> Result<Option<Arc<Document>>, Box<dyn Error>> {
+        let Some(language) = self.rule_language(rule, file_name) else {
+            return Ok(None);
+        };
+        Ok(Some(self.read_document(language, file_name).await?))
+    }
+
+    /// The language of `file_name` if it is the language of `rule` or embeds it.
+    fn rule_language(&self, rule: &RuleConfig<SgLanguage>, file_name: &str) -> Option<SgLanguage> {
+        let language = self.language_for_path(Path::new(file_name))?;
+        (language == rule.language || language == SgLanguage::Builtin(SupportLang::Html))
+            .then_some(language)
+    }
+
+    async fn read_document(
+        &self,
+        language: SgLanguage,
+        file_name: &str,
+    ) -> Result<Arc<Document>, Box<dyn Error>> {
+        let bytes = tokio::fs::read(file_name).await?;
+        self.document(language, &String::from_utf8_lossy(&bytes))
+    }
+
+    /// Returns the parsed `source`, from the cache if the same content was parsed before.
+    pub fn document(
+        &self,
+        language: SgLanguage,
+        source: &str,
+    ) -> Result<Arc<Document>, Box<dyn Error>> {
+        let mut hasher = DefaultHasher::new();
+        source.hash(&mut hasher);
+        let key = (language, hasher.finish());
+        if let Some(document) = self.cache.lock().unwrap().documents.get(&key) {
+            return Ok(Arc::clone(document));
+        }
+
+        // Embedded languages are named like extensions, e.g. `<script lang="ts">`
+        let document = Arc::new(Document::parse(language, source, |name| {
+            self.language_for_path(Path::new(&format!("script.{}", name)))
+        })?);
+        let mut cache = self.cache.lock().unwrap();
+        if cache.documents.len() >= MAX_CACHED_DOCUMENTS {
+            if let Some(oldest) = cache.order.pop_front() {
+                cache.documents.remove(&oldest);
+            }
+        }
+        if cache.documents.insert(key, Arc::clone(&document)).is_none() {
+            cache.order.push_back(key);
+        }
+        Ok(document)
+    }
+
+    /// Matches the rules of the languages of `document` against every node.
+    fn scan_symbols(&self, document: &Document) -> Vec<AstGrepMatch> {
+        let mut symbols = Vec::new();
+        for root in &document.roots {
+            let language = *root.lang();
+            let Some(rules) = self.rules.get(&language) else {
+                continue;
+            };
+            for node in root.root().dfs() {
+                for rule in rules {
+                    if let Some(node_match) = rule.matcher.match_node(node.clone()) {
+                        symbols.extend(symbol_match(document, language, rule, &node_match));
+                    }
+                }
+            }
+        }
+        // The containers of a symbol are the symbols whose definition spans its own
+        let spans: Vec<(usize, usize, String)> = symbols
+            .iter()
+            .map(|symbol| {
+                let context = &symbol.meta_variables.single.context.range.byte_offset;
+                let name = symbol.meta_variables.single.name.text.clone();
+                (context.start, context.end, name)
+            })
+            .collect();
+        for (symbol, (start, end, _)) in symbols.iter_mut().zip(&spans) {
+            let mut containers: Vec<_> = spans
+                .iter()
+                .filter(|(s, e, _)| s <= start && end <= e && (s, e) != (start, end))
+                .collect();
+            containers.sort_by_key(|(s, e, _)| (*s, Reverse(*e)));
+            symbol.containers = containers
+                .into_iter()
+                .map(|(_, _, name)| name.clone())
+                .collect();
+        }
+        symbols
+    }
+}
+
+/// Symbol rules capture the identifier as `$NAME` and the whole definition as `$CONTEXT`.
+fn symbol_match(
+    document: &Document,
+    language: SgLanguage,
+    rule: &RuleConfig<SgLanguage>,
+    node_match: &SgNodeMatch,
+) -> Option<AstGrepMatch> {
+    let env = node_match.get_env();
+    let (Some(name), Some(context)) = (env.get_match("NAME"), env.get_match("CONTEXT")) else {
+        debug!("Rule {} matched without $NAME and $CONTEXT", rule.id);
+        return None;
+    };
+    // Decorators are children of the definition, e.g. a Python `decorated_definition`
+    let decorators = context
+        .children()
+        .filter(|child| child.kind() == "decorator")
+        .map(|decorator| {
+            let text = decorator.text();
+            text.strip_prefix('@').unwrap_or(&text).trim().to_string()
+        })
+        .collect();
+    let documentation = documentation(language, context);
+    let (lines, char_count) = document.lines(node_match);
+    Some(AstGrepMatch {
+        text: node_match.text().to_string(),
+        range: document.range(node_match),
+        file: String::new(),
+        lines,
+        char_count,
+        language: language.to_string(),
+        meta_variables: MetaVariables {
+            single: SingleVariable {
+                name: meta_variable(document, name),
+                context: meta_variable(document, context),
+            },
+            multi: MultiVariables {
+                secondary: Vec::new(),
+            },
+        },
+        rule_id: rule.id.clone(),
+        labels: Vec::new(),
+        containers: Vec::new(),
+        decorators,
+        documentation,
+    })
+}
+
+fn rule_match(
+    document: &Document,
+    rule: &RuleConfig<SgLanguage>,
+    file_name: &str,
+    node_match: &SgNodeMatch,
+) -> RuleMatch {
+    let env = node_match.get_env();
+    let mut meta_variables = BTreeMap::new();
+    for variable in env.get_matched_variables() {
+        match variable {
+            MetaVariableKind::Capture(name, _) => {
+                if let Some(captured) = env.get_match(&name) {
+                    meta_variables.insert(name, meta_variable(document, captured));
+                }
+            }
+            MetaVariableKind::MultiCapture(name) => {
+                let captured = env.get_multiple_matches(&name);
+                let (Some(first), Some(last)) = (captured.first(), captured.last()) else {
+                    continue;
+                };
+                let variable = MetaVariable {
+                    text: document.source()[first.range().start..last.range().end].to_string(),
+                    range: document.span(first, last),
+                };
+                meta_variables.insert(name, variable);
+            }
+            _ => {}
+        }
+    }
+    RuleMatch {
+        text: node_match.text().to_string(),
+        range: document.range(node_match),
+        file: file_name.to_string(),
+        language: rule.language.to_string(),
+        meta_variables,
+    }
+}
+
+fn meta_variable(document: &Document, captured: &SgNode) -> MetaVariable {
+    MetaVariable {
+        text: captured.text().to_string(),
+        range: document.range(captured),
+    }
+}
+
+/// Compiles the rule of a search, either an ast-grep `pattern` or a YAML `rule`. The YAML can
+/// be a whole rule file, with `constraints` and `utils`, or only the rule object of one. A `fix`
+/// is added to the rule to rewrite its matches.
+pub fn search_rule(
+    pattern: Option<&str>,
+    rule: Option<&str>,
+    language: &str,
+    fix: Option<&str>,
+) -> Result<RuleConfig<SgLanguage>, Box<dyn Error>> {
+    let language = language_from_name(language)
+        .ok_or_else(|| format!("Unsupported language '{}'", language))?;
+    let mut config = match (pattern, rule) {
+        (Some(pattern), None) => {
+            let mut rule = Mapping::new();
+            rule.insert("pattern".into(), pattern.into());
+            Mapping::from_iter([("rule".into(), rule.into())])
+        }
+        (None, Some(rule)) => match serde_yaml::from_str(rule)? {
+            Value::Mapping(config) if config.contains_key("rule") => config,
+            Value::Mapping(rule) => Mapping::from_iter([("rule".into(), rule.into())]),
+            _ => return Err("The rule has to be a YAML mapping".into()),
+        },
+        _ => return Err("Exactly one of pattern and rule has to be set".into()),
+    };
+    if !config.contains_key("id") {
+        config.insert("id".into(), "search".into());
+    }
+    if !config.contains_key("language") {
+        config.insert("language".into(), language.to_string().into());
+    }
+    if let Some(fix) = fix {
+        config.insert("fix".into(), fix.into());
+    }
+    let config: SerializableRuleConfig<SgLanguage> = serde_yaml::from_value(config.into())?;
+    if config.language != language {
+        return Err(format!(
+            "The rule is written for {}, not {}",
+            config.language, language
+        )
+        .into());
+    }
+    Ok(RuleConfig::try_from(config, &GlobalRules::default())?)
+}
+
+/// The rule files of `rule_dirs` and their subdirectories, sorted by path within each directory.
+fn read_rule_dirs(rule_dirs: &[PathBuf]) -> Result<Vec<(PathBuf, String)>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    for dir in rule_dirs {
+        if !dir.is_dir() {
+            return Err(format!("Rule directory {} does not exist", dir.display()).into());
+        }
+        let mut paths = Vec::new();
+        for extension in ["yml", "yaml"] {
+            let pattern = format!(
+                "{}/**/*.{}",
+                glob::Pattern::escape(&dir.to_string_lossy()),
+                extension
+            );
+            paths.extend(glob::glob(&pattern)?.filter_map(Result::ok));
+        }
+        paths.sort();
+        for path in paths {
+            let content = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read rule file {}: {}", path.display(), e))?;
+            files.push((path, content));
+        }
+    }
+    Ok(files)
+}
+
+/// Compiles the rules of a rule file, which can hold several YAML documents.
+pub fn parse_rules(content: &str) -> Result<Vec<RuleConfig<SgLanguage>>, Box<dyn Error>> {
+    Ok(from_yaml_string(content, &GlobalRules::default())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_rules_are_listed() {
+        let rules_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/ast_grep/rules");
+        let mut files: Vec<String> = glob::glob(&format!("{}/**/*.yml", rules_dir.display()))
+            .unwrap()
+            .map(|path| {
+                let path = path.unwrap();
+                path.strip_prefix(&rules_dir)
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        files.sort();
+        let listed: Vec<String> = BUILTIN_RULES.iter().map(|(p, _)| p.to_string()).collect();
+        assert_eq!(listed, files);
+    }
+
+    #[test]
+    fn test_has_symbol_rules() -> Result<(), Box<dyn std::error::Error>> {
+        let client = AstGrepClient::new(&LsproxyConfig::default(), &[])?;
+        assert!(client.has_symbol_rules("/workspace/main.py"));
+        assert!(client.has_symbol_rules("/workspace/notebook.ipynb"));
+        // ast-grep parses Kotlin and Lua, but no rules find their symbols
+        assert!(!client.has_symbol_rules("/workspace/Main.kt"));
+        assert!(!client.has_symbol_rules("/workspace/init.lua"));
+        assert!(!client.has_symbol_rules("/workspace/README.md"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_single_file_component_symbols() -> Result<(), Box<dyn std::error::Error>> {
+        let client = AstGrepClient::new(&LsproxyConfig::default(), &[])?;
+        let file = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../sample_project/frontend/Counter.vue")
+            .to_string_lossy()
+            .into_owned();
+        assert_eq!(
+            client.language_for_path(Path::new(&file)),
+            Some(SgLanguage::Builtin(SupportLang::Html))
+        );
+
+        let symbols = client.get_file_symbols(&file).await?;
+        let names: Vec<(&str, &str, &str)> = symbols
+            .iter()
+            .map(|s| {
+                (
+                    s.language.as_str(),
+                    s.rule_id.as_str(),
+                    s.meta_variables.single.name.text.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(names, vec![("Tsx", "variable", "count")]);
+        assert_eq!(symbols[0].range.start.line, 4);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scan_file() -> Result<(), Box<dyn std::error::Error>> {
+        let client = AstGrepClient::new(&LsproxyConfig::default(), &[])?;
+        let file = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../sample_project/python/main.py")
+            .to_string_lossy()
+            .into_owned();
+
+        let rule = search_rule(Some("plt.plot($X, $$$REST)"), None, "python", None)?;
+        let matches = client.scan_file(&rule, &file).await?;
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].range.start.line, 9);
+        assert_eq!(
+            matches[0].meta_variables["X"].text,
+            "[v[0] for v in result]"
+        );
+        assert_eq!(
+            matches[0].meta_variables["REST"].text,
+            "[v[1] for v in result]"
+        );
+
+        let rule = search_rule(
+            None,
+            Some("pattern: plt.plot($$$)\ninside:\n  kind: for_statement\n  stopBy: end"),
+            "python",
+            None,
+        )?;
+        let matches = client.scan_file(&rule, &file).await?;
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].range.start.line, 11);
+
+        let rule = search_rule(Some("plt.plot($$$)"), None, "rust", None)?;
+        assert!(client.scan_file(&rule, &file).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scan_perl_file() -> Result<(), Box<dyn std::error::Error>> {
+        let client = AstGrepClient::new(&LsproxyConfig::default(), &[])?;
+        let file = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../sample_project/perl/astar_search.pl")
+            .to_string_lossy()
+            .into_owned();
+
+        let symbols = client.get_file_symbols(&file).await?;
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].meta_variables.single.name.text, "distance");
+
+        // `$finish` is a Perl variable, `$$$ARGS` a meta-variable
+        let rule = search_rule(Some("distance($$$ARGS, $finish)"), None, "perl", None)?;
+        let matches = client.scan_file(&rule, &file).await?;
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].range.start.line, 23);
+        assert_eq!(matches[0].meta_variables["ARGS"].text, "$_");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search_perl_uppercase_variables() -> Result<(), Box<dyn std::error::Error>> {
+        let client = AstGrepClient::new(&LsproxyConfig::default(), &[])?;
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("Exporter.pm");
+        std::fs::write(
+            &file,
+            "package Foo;\nour $VERSION = '1.0';\nour @ISA = ('Exporter');\nmy $version = $VERSION;\nprint $ENV{HOME};\nmy $ref = \\$VERSION;\n",
+        )?;
+        let file = file.to_string_lossy().into_owned();
+        let lines = |matches: Vec<RuleMatch>| {
+            matches
+                .iter()
+                .map(|m| m.range.start.line)
+                .collect::<Vec<_>>()
+        };
+
+        // Escaped, `$VERSION` is the Perl variable rather than a meta-variable
+        let rule = search_rule(Some("\\$VERSION"), None, "perl", None)?;
+        assert_eq!(lines(client.scan_file(&rule, &file).await?), vec![1, 3, 5]);
+        let rule = search_rule(Some("our \\$VERSION = $V"), None, "perl", None)?;
+        let matches = client.scan_file(&rule, &file).await?;
+        assert_eq!(lines(matches.clone()), vec![1]);
+        assert_eq!(matches[0].meta_variables["V"].text, "'1.0'");
+        let rule = search_rule(Some("\\$ENV{HOME}"), None, "perl", None)?;
+        assert_eq!(lines(client.scan_file(&rule, &file).await?), vec![4]);
+        let rule = search_rule(Some("$version"), None, "perl", None)?;
+        assert_eq!(lines(client.scan_file(&rule, &file).await?), vec![3]);
+        let rule = search_rule(Some("@ISA"), None, "perl", None)?;
+        assert_eq!(lines(client.scan_file(&rule, &file).await?), vec![2]);
+
+        // A reference to it keeps one backslash
+        let rule = search_rule(Some("my $ref = \\\\$VERSION"), None, "perl", None)?;
+        assert_eq!(lines(client.scan_file(&rule, &file).await?), vec![5]);
+
+        // Unescaped, it is a meta-variable
+        let rule = search_rule(Some("my $version = $VALUE"), None, "perl", None)?;
+        let matches = client.scan_file(&rule, &file).await?;
+        assert_eq!(matches[0].meta_variables["VALUE"].text, "$VERSION");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_file() -> Result<(), Box<dyn std::error::Error>> {
+        let client = AstGrepClient::new(&LsproxyConfig::default(), &[])?;
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("client.py");
+        std::fs::write(
+            &file,
+            "def fetch(url, timeout):\n    if url:\n        return get(get(url))\n    return get(url, timeout=timeout)\n",
+        )?;
+        let file = file.to_string_lossy().into_owned();
+
+        let rule = search_rule(
+            Some("get($$$ARGS)"),
+            None,
+            "python",
+            Some("session.get($$$ARGS)"),
+        )?;
+        let edit = client.rewrite_file(&rule, &file).await?.unwrap();
+        assert_eq!(edit.matches, 2);
+        // The inner call is part of the outer match, only the outer one is rewritten
+        assert_eq!(
+            edit.rewritten,
+            "def fetch(url, timeout):\n    if url:\n        return session.get(get(url))\n    return session.get(url, timeout=timeout)\n"
+        );
+
+        let rule = search_rule(Some("post($$$ARGS)"), None, "python", Some("put($$$ARGS)"))?;
+        assert!(client.rewrite_file(&rule, &file).await?.is_none());
+
+        let rule = search_rule(Some("get($$$ARGS)"), None, "python", None)?;
+        assert!(client.rewrite_file(&rule, &file).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_skips_non_utf8() -> Result<(), Box<dyn std::error::Error>> {
+        let client = AstGrepClient::new(&LsproxyConfig::default(), &[])?;
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("legacy.py");
+        // Latin-1, as some older sources are
+        let source = b"# caf\xe9\nget(url)\n";
+        std::fs::write(&file, source)?;
+
+        let rule = search_rule(Some("get($URL)"), None, "python", Some("fetch($URL)"))?;
+        let error = client
+            .rewrite_file(&rule, &file.to_string_lossy())
+            .await
+            .unwrap_err();
+        assert!(error.is::<NotUtf8Error>());
+        assert_eq!(std::fs::read(&file)?, source);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_symbol_kinds() -> Result<(), Box<dyn std::error::Error>> {
+        let client = AstGrepClient::new(&LsproxyConfig::default(), &[])?;
+        let dir = tempfile::tempdir()?;
+        let sources = [
+            (
+                "settings.py",
+                "MAX_RETRIES = 3\ntimeout = 10\n\n@dataclass(frozen=True)\nclass Point:\n    ORIGIN = 0\n",
+                vec![
+                    ("constant", "MAX_RETRIES"),
+                    ("variable", "timeout"),
+                    ("class", "Point"),
+                    ("constant", "ORIGIN"),
+                ],
+            ),
+            (
+                "Shapes.java",
+                "class Shapes {\n    private int count = 0;\n    enum Color { RED }\n    record Point(int x, int y) {}\n}\n",
+                vec![
+                    ("class", "Shapes"),
+                    ("field", "count"),
+                    ("enum", "Color"),
+                    ("record", "Point"),
+                ],
+            ),
+            (
+                "shapes.ts",
+                "enum Color { Red }\ntype Point = { x: number };\n",
+                vec![("enum", "Color"), ("type", "Point")],
+            ),
+            (
+                "shapes.cpp",
+                "#define MAX 10\n#define SQUARE(x) ((x) * (x))\nnamespace geo {\nenum Color { RED };\nusing Size = int;\n}\n",
+                vec![
+                    ("macro", "MAX"),
+                    ("macro", "SQUARE"),
+                    ("namespace", "geo"),
+                    ("enum", "Color"),
+                    ("type", "Size"),
+                ],
+            ),
+            (
+                "shapes.rs",
+                "const MAX: u32 = 10;\nstatic NAME: &str = \"shapes\";\nmacro_rules! square { ($x:expr) => { $x * $x }; }\nmod geo {}\n",
+                vec![
+                    ("constant", "MAX"),
+                    ("variable", "NAME"),
+                    ("macro", "square"),
+                    ("module", "geo"),
+                ],
+            ),
+            (
+                "shapes.go",
+                "package shapes\n\nconst Max = 10\n\nvar name = \"shapes\"\n\nfunc Area() int {\n\tvar local = 1\n\treturn local\n}\n",
+                vec![("constant", "Max"), ("variable", "name"), ("function", "Area")],
+            ),
+            (
+                "Shapes.pm",
+                "package Shapes;\nuse constant PI => 3.14;\n\nsub area {\n    my ($r) = @_;\n    return PI * $r ** 2;\n}\n",
+                vec![("module", "Shapes"), ("constant", "PI"), ("function", "area")],
+            ),
+        ];
+        for (file_name, source, expected) in sources {
+            let file = dir.path().join(file_name);
+            std::fs::write(&file, source)?;
+            let symbols = client.get_file_symbols(&file.to_string_lossy()).await?;
+            let found: Vec<(&str, &str)> = symbols
+                .iter()
+                .map(|s| {
+                    (
+                        s.rule_id.as_str(),
+                        s.meta_variables.single.name.text.as_str(),
+                    )
+                })
+                .collect();
+            assert_eq!(found, expected, "symbols of {}", file_name);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_python_decorators() -> Result<(), Box<dyn std::error::Error>> {
+        let client = AstGrepClient::new(&LsproxyConfig::default(), &[])?;
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("routes.py");
+        std::fs::write(
+            &file,
+            "@app.route(\"/users\")\n@login_required\ndef users():\n    pass\n",
+        )?;
+        let symbols = client.get_file_symbols(&file.to_string_lossy()).await?;
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(
+            symbols[0].decorators,
+            vec!["app.route(\"/users\")", "login_required"]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_documentation() -> Result<(), Box<dyn std::error::Error>> {
+        let client = AstGrepClient::new(&LsproxyConfig::default(), &[])?;
+        let dir = tempfile::tempdir()?;
+        let sources = [
+            (
+                "users.py",
+                "@dataclass\nclass User:\n    \"\"\"A user account.\n\n    Stored in the database.\n    \"\"\"\n\n    def greet(self):\n        # Not a docstring\n        'Say hello.'\n        pass\n\ndef helper():\n    pass\n",
+                vec![
+                    ("User", Some("A user account.\n\nStored in the database.")),
+                    ("greet", Some("Say hello.")),
+                    ("helper", None),
+                ],
+            ),
+            (
+                "users.rs",
+                "/// A user account.\n/// Stored in the database.\n#[derive(Debug)]\nstruct User {}\n\n// Not documentation\nfn helper() {}\n\n/// Separated by a blank line\n\nfn other() {}\n",
+                vec![
+                    ("User", Some("A user account.\nStored in the database.")),
+                    ("helper", None),
+                    ("other", None),
+                ],
+            ),
+            (
+                "users.ts",
+                "/**\n * Finds a user.\n * @param name the user name\n */\nexport function findUser(name: string) {}\n",
+                vec![("findUser", Some("Finds a user.\n@param name the user name"))],
+            ),
+            (
+                "User.java",
+                "/** A user account. */\nclass User {\n    /** Says hello. */\n    @Override\n    void greet() {}\n}\n",
+                vec![("User", Some("A user account.")), ("greet", Some("Says hello."))],
+            ),
+            (
+                "users.php",
+                "<?php\nclass Users\n{\n    /**\n     * @return User[]\n     */\n    public function all(): array {}\n}\n",
+                vec![("Users", None), ("all", Some("@return User[]"))],
+            ),
+            (
+                "users.go",
+                "package users\n\n// FindUser finds a user.\nfunc FindUser() {}\n",
+                vec![("FindUser", Some("FindUser finds a user."))],
+            ),
+        ];
+        for (file_name, source, expected) in sources {
+            let file = dir.path().join(file_name);
+            std::fs::write(&file, source)?;
+            let symbols = client.get_file_symbols(&file.to_string_lossy()).await?;
+            let found: Vec<(&str, Option<&str>)> = symbols
+                .iter()
+                .map(|s| {
+                    (
+                        s.meta_variables.single.name.text.as_str(),
+                        s.documentation.as_deref(),
+                    )
+                })
+                .collect();
+            assert_eq!(found, expected, "documentation of {}", file_name);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rule_dirs() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let rules = dir.path().join("rules/django");
+        std::fs::create_dir_all(&rules)?;
+        std::fs::write(
+            rules.join("model.yaml"),
+            "id: django-model\nlanguage: python\nrule:\n  kind: identifier\n  pattern: $NAME\n  inside:\n    kind: class_definition\n    field: name\n    pattern: $CONTEXT\n    has:\n      kind: argument_list\n      regex: models\\.Model\n",
+        )?;
+        let file = dir.path().join("models.py");
+        std::fs::write(
+            &file,
+            "class User(models.Model):\n    pass\n\nclass Helper:\n    pass\n",
+        )?;
+
+        let rule_dirs = vec![dir.path().join("rules")];
+        let client = AstGrepClient::new(&LsproxyConfig::default(), &rule_dirs)?;
+        let symbols = client.get_file_symbols(&file.to_string_lossy()).await?;
+        let found: Vec<(&str, &str)> = symbols
+            .iter()
+            .map(|s| {
+                (
+                    s.rule_id.as_str(),
+                    s.meta_variables.single.name.text.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("class", "User"),
+                ("django-model", "User"),
+                ("class", "Helper")
+            ]
+        );
+        let builtin = AstGrepClient::new(&LsproxyConfig::default(), &[])?;
+        assert_ne!(client.fingerprint(), builtin.fingerprint());
+
+        std::fs::write(rules.join("broken.yml"), "id: broken\nlanguage: cobol\n")?;
+        assert!(AstGrepClient::new(&LsproxyConfig::default(), &rule_dirs).is_err());
+        assert!(
+            AstGrepClient::new(&LsproxyConfig::default(), &[dir.path().join("missing")]).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_search_rules() {
+        assert!(search_rule(Some("f($A)"), None, "cobol", None).is_err());
+        assert!(search_rule(None, None, "python", None).is_err());
+        assert!(search_rule(Some("f($A)"), Some("kind: call"), "python", None).is_err());
+        assert!(search_rule(None, Some("kind: not_a_kind"), "python", None).is_err());
+        assert!(search_rule(
+            None,
+            Some("language: rust\nrule:\n  kind: call"),
+            "python",
+            None
+        )
+        .is_err());
+        assert!(search_rule(None, Some("id: calls\nrule:\n  kind: call"), "python", None).is_ok());
+    }
+
+    #[test]
+    fn test_documents_are_cached_by_content() -> Result<(), Box<dyn std::error::Error>> {
+        let client = AstGrepClient::new(&LsproxyConfig::default(), &[])?;
+        let first = client.document(
+            SgLanguage::Builtin(SupportLang::Python),
+            "def f():\n    pass\n",
+        )?;
+        let second = client.document(
+            SgLanguage::Builtin(SupportLang::Python),
+            "def f():\n    pass\n",
+        )?;
+        let other = client.document(
+            SgLanguage::Builtin(SupportLang::Python),
+            "def g():\n    pass\n",
+        )?;
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(!Arc::ptr_eq(&first, &other));
+        Ok(())
+    }
 }
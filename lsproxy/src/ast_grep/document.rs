@@ -0,0 +1,81 @@
+use std::error::Error;
+use std::sync::OnceLock;
+
+use ast_grep_core::tree_sitter::StrDoc;
+use ast_grep_core::{AstGrep, Node, NodeMatch};
+
+use super::language::SgLanguage;
+use super::types::{AstGrepMatch, AstGrepPosition, AstGrepRange, ByteOffset, CharCount};
+
+pub type SgRoot = AstGrep<StrDoc<SgLanguage>>;
+pub type SgNode<'r> = Node<'r, StrDoc<SgLanguage>>;
+pub type SgNodeMatch<'r> = NodeMatch<'r, StrDoc<SgLanguage>>;
+
+/// A parsed source file, with the trees of the languages embedded in it.
+pub struct Document {
+    /// The tree of the document language first, then one tree per injected language covering its ranges.
+    pub roots: Vec<SgRoot>,
+    pub(super) symbols: OnceLock<Vec<AstGrepMatch>>,
+}
+
+impl Document {
+    /// Parses `source`, the ranges the grammar marks as another language, e.g. the scripts of an
+    /// HTML file, are parsed in the language `injected` returns for their name.
+    pub fn parse(
+        language: SgLanguage,
+        source: &str,
+        injected: impl Fn(&str) -> Option<SgLanguage>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let root = SgRoot::try_new(source, language)
+            .map_err(|e| format!("Failed to parse {} source: {}", language, e))?;
+        let mut roots = root.get_injections(injected);
+        roots.insert(0, root);
+        Ok(Self {
+            roots,
+            symbols: OnceLock::new(),
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        self.roots[0].source()
+    }
+
+    /// Positions are zero based, columns count characters like ast-grep does.
+    pub fn range(&self, node: &SgNode) -> AstGrepRange {
+        self.span(node, node)
+    }
+
+    /// The range from the start of `first` to the end of `last`.
+    pub fn span(&self, first: &SgNode, last: &SgNode) -> AstGrepRange {
+        let (start, end) = (first.start_pos(), last.end_pos());
+        AstGrepRange {
+            byte_offset: ByteOffset {
+                start: first.range().start,
+                end: last.range().end,
+            },
+            start: AstGrepPosition {
+                line: start.line(),
+                column: start.column(first),
+            },
+            end: AstGrepPosition {
+                line: end.line(),
+                column: end.column(last),
+            },
+        }
+    }
+
+    /// The lines spanned by `node`, and the characters around it on the first and last of them.
+    pub fn lines(&self, node: &SgNode) -> (String, CharCount) {
+        let source = self.source();
+        let range = node.range();
+        let line_start = source[..range.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[range.end..]
+            .find('\n')
+            .map_or(source.len(), |offset| range.end + offset);
+        let char_count = CharCount {
+            leading: source[line_start..range.start].chars().count(),
+            trailing: source[range.end..line_end].chars().count(),
+        };
+        (source[line_start..line_end].to_string(), char_count)
+    }
+}
@@ -0,0 +1,204 @@
+use ast_grep_language::SupportLang;
+
+use super::document::SgNode;
+use super::language::SgLanguage;
+
+/// Nodes wrapping a definition, the comments documenting it come before the wrapper.
+const WRAPPER_KINDS: &[&str] = &[
+    "export_statement",
+    "template_declaration",
+    "ambient_declaration",
+];
+
+/// Nodes allowed between a doc comment and the definition it documents.
+const SKIPPED_KINDS: &[&str] = &["attribute_item", "decorator"];
+
+/// The documentation of the definition `context`, without comment markers or quotes.
+///
+/// Python definitions are documented by the docstring starting their body, and variables by the
+/// string following them. Other languages use the comments right before the definition: `///`
+/// and `/** */` blocks, which covers JSDoc and Javadoc, or any line comment in Go, Ruby and Bash.
+pub fn documentation(language: SgLanguage, context: &SgNode) -> Option<String> {
+    let documentation = match language {
+        SgLanguage::Builtin(SupportLang::Python) => docstring(context),
+        _ => leading_comments(language, context),
+    }?;
+    (!documentation.is_empty()).then_some(documentation)
+}
+
+fn docstring(context: &SgNode) -> Option<String> {
+    let definition = match context.kind().as_ref() {
+        "decorated_definition" => context.field("definition")?,
+        _ => context.clone(),
+    };
+    let statement = match definition.kind().as_ref() {
+        "function_definition" | "class_definition" => definition
+            .field("body")?
+            .named_children()
+            .find(|child| child.kind() != "comment")?,
+        // Attribute docstrings follow the assignment
+        "expression_statement" => next_named(&definition)?,
+        _ => return None,
+    };
+    let mut children = statement.named_children();
+    let string = children.next()?;
+    if statement.kind() != "expression_statement"
+        || children.next().is_some()
+        || string.kind() != "string"
+    {
+        return None;
+    }
+    Some(clean_docstring(&string.text()))
+}
+
+/// Strips the prefix and quotes of a string literal, and the indentation of its lines after the
+/// first like `inspect.cleandoc`.
+fn clean_docstring(literal: &str) -> String {
+    let literal = literal.trim_start_matches(|c: char| "rRuUbBfF".contains(c));
+    let quotes = if literal.starts_with("\"\"\"") || literal.starts_with("'''") {
+        3
+    } else {
+        1
+    };
+    let content = literal
+        .get(quotes..literal.len().saturating_sub(quotes))
+        .unwrap_or_default();
+    let mut lines = content.lines();
+    let first = lines.next().unwrap_or_default().trim();
+    let rest: Vec<&str> = lines.collect();
+    let indentation = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let mut cleaned = vec![first];
+    cleaned.extend(
+        rest.iter()
+            .map(|line| line.get(indentation..).unwrap_or_default().trim_end()),
+    );
+    cleaned.join("\n").trim().to_string()
+}
+
+fn leading_comments(language: SgLanguage, context: &SgNode) -> Option<String> {
+    let mut definition = context.clone();
+    while let Some(parent) = definition.parent() {
+        if !WRAPPER_KINDS.contains(&parent.kind().as_ref()) {
+            break;
+        }
+        definition = parent;
+    }
+
+    let mut comments = Vec::new();
+    let mut next = definition.clone();
+    let mut sibling = prev_named(&definition);
+    while let Some(node) = sibling {
+        if SKIPPED_KINDS.contains(&node.kind().as_ref()) {
+            sibling = prev_named(&node);
+            next = node;
+            continue;
+        }
+        // A blank line separates the comment from the definition
+        let adjacent = last_row(&node) + 1 >= next.start_pos().line();
+        let text = node.text().trim().to_string();
+        if !node.kind().contains("comment") || !adjacent || !is_doc_comment(language, &text) {
+            break;
+        }
+        let block = text.starts_with("/*");
+        comments.push(text);
+        if block {
+            break;
+        }
+        sibling = prev_named(&node);
+        next = node;
+    }
+    comments.reverse();
+    let lines: Vec<String> = comments
+        .iter()
+        .flat_map(|comment| clean_comment(comment))
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\n").trim().to_string())
+}
+
+fn prev_named<'r>(node: &SgNode<'r>) -> Option<SgNode<'r>> {
+    let mut sibling = node.prev();
+    while let Some(node) = sibling {
+        if node.is_named() {
+            return Some(node);
+        }
+        sibling = node.prev();
+    }
+    None
+}
+
+fn next_named<'r>(node: &SgNode<'r>) -> Option<SgNode<'r>> {
+    let mut sibling = node.next();
+    while let Some(node) = sibling {
+        if node.is_named() {
+            return Some(node);
+        }
+        sibling = node.next();
+    }
+    None
+}
+
+/// The last row of `node`, some grammars end line comments after their newline.
+fn last_row(node: &SgNode) -> usize {
+    let (row, column) = node.end_pos().byte_point();
+    match column {
+        0 if row > node.start_pos().line() => row - 1,
+        _ => row,
+    }
+}
+
+fn is_doc_comment(language: SgLanguage, comment: &str) -> bool {
+    let doc_block = comment.starts_with("/**") && comment != "/**/";
+    let doc_line = comment.starts_with("///") && !comment.starts_with("////");
+    let line = match language {
+        SgLanguage::Builtin(SupportLang::Go) => comment.starts_with("//"),
+        SgLanguage::Builtin(SupportLang::Ruby) | SgLanguage::Builtin(SupportLang::Bash) => {
+            comment.starts_with('#') && !comment.starts_with("#!")
+        }
+        _ => false,
+    };
+    doc_block || doc_line || line
+}
+
+/// The lines of a comment without its markers.
+fn clean_comment(comment: &str) -> Vec<String> {
+    if let Some(block) = comment.strip_prefix("/**") {
+        let block = block.strip_suffix("*/").unwrap_or(block);
+        let lines: Vec<String> = block
+            .lines()
+            .map(|line| {
+                let line = line.trim();
+                let line = line.strip_prefix('*').unwrap_or(line);
+                line.strip_prefix(' ')
+                    .unwrap_or(line)
+                    .trim_end()
+                    .to_string()
+            })
+            .collect();
+        let start = lines.iter().position(|line| !line.is_empty());
+        let end = lines.iter().rposition(|line| !line.is_empty());
+        return match (start, end) {
+            (Some(start), Some(end)) => lines[start..=end].to_vec(),
+            _ => Vec::new(),
+        };
+    }
+    comment
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            let line = line
+                .strip_prefix("///")
+                .or_else(|| line.strip_prefix("//"))
+                .or_else(|| line.strip_prefix('#'))
+                .unwrap_or(line);
+            line.strip_prefix(' ')
+                .unwrap_or(line)
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
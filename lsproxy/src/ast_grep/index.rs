@@ -0,0 +1,414 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use log::{debug, info, warn};
+use notify_debouncer_mini::DebouncedEvent;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tokio::sync::broadcast::Receiver;
+use tokio::sync::RwLock;
+
+use super::client::AstGrepClient;
+use crate::api_types::Symbol;
+use crate::config::state_dir;
+use crate::utils::file_utils::search_files;
+
+/// Bumped when the format of the persisted index changes.
+const INDEX_VERSION: u32 = 5;
+
+#[derive(Deserialize)]
+struct PersistedIndex {
+    version: u32,
+    fingerprint: u64,
+    files: HashMap<String, IndexedFile>,
+}
+
+#[derive(Serialize)]
+struct PersistedIndexRef<'a> {
+    version: u32,
+    fingerprint: u64,
+    files: &'a HashMap<String, IndexedFile>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct IndexedFile {
+    /// Modification time of the file when it was scanned, in nanoseconds since the epoch.
+    modified: u128,
+    symbols: Vec<Symbol>,
+}
+
+/// The symbols the ast-grep rules find in the workspace files, by path relative to the root.
+/// Their documentation is left out, most queries don't ask for it.
+///
+/// The index is kept in the state directory between runs. Files are scanned again when their
+/// modification time changed since, and when the watcher reports a change. It isn't queried
+/// before the first refresh is done, the saved symbols may be stale until then.
+pub struct SymbolIndex {
+    root: PathBuf,
+    path: PathBuf,
+    ast_grep: Arc<AstGrepClient>,
+    files: RwLock<HashMap<String, IndexedFile>>,
+    refreshed: AtomicBool,
+}
+
+impl SymbolIndex {
+    /// Loads the index of `root` from the state directory. It starts empty if there is none yet,
+    /// or if it was built with other rules.
+    pub fn load(root: &Path, ast_grep: Arc<AstGrepClient>) -> Self {
+        let path = state_dir().join("index").join(format!(
+            "{}.json",
+            root.to_string_lossy().replace('/', "__")
+        ));
+        Self::load_from(root, path, ast_grep)
+    }
+
+    fn load_from(root: &Path, path: PathBuf, ast_grep: Arc<AstGrepClient>) -> Self {
+        let files = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| {
+                serde_json::from_slice::<PersistedIndex>(&bytes)
+                    .map_err(|e| warn!("Ignoring symbol index {:?}: {}", path, e))
+                    .ok()
+            })
+            .filter(|index| {
+                index.version == INDEX_VERSION && index.fingerprint == ast_grep.fingerprint()
+            })
+            .map(|index| index.files)
+            .unwrap_or_default();
+        debug!(
+            "Loaded the symbols of {} files from {:?}",
+            files.len(),
+            path
+        );
+        Self {
+            root: root.to_path_buf(),
+            path,
+            ast_grep,
+            files: RwLock::new(files),
+            refreshed: AtomicBool::new(false),
+        }
+    }
+
+    /// Brings the index up to date with `files`, relative to the root. Only the files modified
+    /// since they were indexed are scanned.
+    pub async fn refresh(&self, files: &[String]) {
+        // Scanning takes a while, the index isn't locked meanwhile
+        let mut current = self.files.read().await.clone();
+        let mut indexed = HashMap::new();
+        let mut scanned = 0;
+        for file in files {
+            let modified = modified(&self.root.join(file));
+            match current.remove(file) {
+                Some(entry) if Some(entry.modified) == modified => {
+                    indexed.insert(file.clone(), entry);
+                }
+                _ => {
+                    scanned += 1;
+                    if let Some(entry) = self.scan(file).await {
+                        indexed.insert(file.clone(), entry);
+                    }
+                }
+            }
+        }
+        info!(
+            "Indexed the symbols of {} files, {} of them scanned",
+            indexed.len(),
+            scanned
+        );
+        *self.files.write().await = indexed;
+        self.refreshed.store(true, Ordering::Release);
+        self.save().await;
+    }
+
+    /// Updates the index from the watcher events. New files are indexed when the workspace
+    /// listing has them: they match one of the `include` globs, none of the `exclude` globs and
+    /// aren't ignored by git.
+    pub fn watch(
+        self: Arc<Self>,
+        mut events: Receiver<DebouncedEvent>,
+        include: Vec<String>,
+        exclude: Vec<String>,
+    ) {
+        tokio::spawn(async move {
+            loop {
+                let mut paths = match events.recv().await {
+                    Ok(event) => vec![event.path],
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!(
+                            "Missed {} file events, listing the workspace files again",
+                            missed
+                        );
+                        let files = self.list_files(&include, &exclude);
+                        self.refresh(&files).await;
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                // Events come one by one, the index is saved once per batch
+                loop {
+                    match events.try_recv() {
+                        Ok(event) => paths.push(event.path),
+                        Err(TryRecvError::Lagged(_)) => continue,
+                        Err(_) => break,
+                    }
+                }
+                paths.sort();
+                paths.dedup();
+                self.update(&paths, &include, &exclude).await;
+                self.save().await;
+            }
+        });
+    }
+
+    /// The workspace files matching the patterns, relative to the root, filtered the same way
+    /// as the files the language servers are given.
+    fn list_files(&self, include: &[String], exclude: &[String]) -> Vec<String> {
+        search_files(&self.root, include.to_vec(), exclude.to_vec(), true)
+            .unwrap_or_else(|e| {
+                warn!("Failed to list the workspace files: {}", e);
+                Vec::new()
+            })
+            .iter()
+            .filter_map(|path| path.strip_prefix(&self.root).ok())
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    async fn update(&self, paths: &[PathBuf], include: &[String], exclude: &[String]) {
+        // Listed once per batch, and only when there are new files
+        let mut listed: Option<HashSet<String>> = None;
+        for path in paths {
+            let Ok(relative_path) = path.strip_prefix(&self.root) else {
+                continue;
+            };
+            let file = relative_path.to_string_lossy().into_owned();
+            if !path.is_file() {
+                // Removed, or a directory whose files are reported on their own
+                let prefix = format!("{}/", file);
+                self.files
+                    .write()
+                    .await
+                    .retain(|indexed, _| *indexed != file && !indexed.starts_with(&prefix));
+                continue;
+            }
+            if !self.files.read().await.contains_key(&file)
+                && !listed
+                    .get_or_insert_with(|| self.list_files(include, exclude).into_iter().collect())
+                    .contains(&file)
+            {
+                continue;
+            }
+            match self.scan(&file).await {
+                Some(entry) => self.files.write().await.insert(file, entry),
+                None => self.files.write().await.remove(&file),
+            };
+        }
+    }
+
+    async fn scan(&self, file: &str) -> Option<IndexedFile> {
+        let full_path = self.root.join(file);
+        let modified = modified(&full_path)?;
+        let matches = self
+            .ast_grep
+            .get_file_symbols(&full_path.to_string_lossy())
+            .await
+            .map_err(|e| warn!("Failed to index {}: {}", file, e))
+            .ok()?;
+        let symbols = matches
+            .into_iter()
+            .map(|ast_match| Symbol {
+                documentation: None,
+                ..ast_match.into_symbol(file.to_string())
+            })
+            .collect();
+        Some(IndexedFile { modified, symbols })
+    }
+
+    async fn save(&self) {
+        let bytes = {
+            let files = self.files.read().await;
+            serde_json::to_vec(&PersistedIndexRef {
+                version: INDEX_VERSION,
+                fingerprint: self.ast_grep.fingerprint(),
+                files: &files,
+            })
+        };
+        let write_index = async {
+            let bytes = bytes?;
+            if let Some(parent) = self.path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            // Written aside first so a crash never leaves half an index behind
+            let partial_path = self.path.with_extension("json.partial");
+            tokio::fs::write(&partial_path, bytes).await?;
+            tokio::fs::rename(&partial_path, &self.path).await?;
+            Ok::<(), Box<dyn std::error::Error>>(())
+        };
+        if let Err(e) = write_index.await {
+            warn!("Failed to save the symbol index to {:?}: {}", self.path, e);
+        }
+    }
+
+    /// The symbols whose name contains `query` ignoring case, and whose kind is `kind`, sorted
+    /// by path and position. Either filter matches everything when not set.
+    ///
+    /// Returns `None` until the first refresh is done.
+    pub async fn query(&self, query: Option<&str>, kind: Option<&str>) -> Option<Vec<Symbol>> {
+        if !self.refreshed.load(Ordering::Acquire) {
+            return None;
+        }
+        let query = query.map(str::to_lowercase);
+        let mut symbols: Vec<Symbol> = self
+            .files
+            .read()
+            .await
+            .values()
+            .flat_map(|entry| entry.symbols.iter())
+            .filter(|symbol| {
+                query
+                    .as_ref()
+                    .is_none_or(|query| symbol.name.to_lowercase().contains(query))
+                    && kind.is_none_or(|kind| symbol.kind == kind)
+            })
+            .cloned()
+            .collect();
+        symbols.sort_by(|a, b| {
+            (&a.range.path, a.range.start.line, a.range.start.character).cmp(&(
+                &b.range.path,
+                b.range.start.line,
+                b.range.start.character,
+            ))
+        });
+        Some(symbols)
+    }
+}
+
+fn modified(path: &Path) -> Option<u128> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::config::LsproxyConfig;
+
+    #[tokio::test]
+    async fn test_index_refresh_and_reload() -> Result<(), Box<dyn std::error::Error>> {
+        let root = tempfile::tempdir()?;
+        let state = tempfile::tempdir()?;
+        let index_path = state.path().join("index.json");
+        let ast_grep = Arc::new(AstGrepClient::new(&LsproxyConfig::default(), &[])?);
+        std::fs::write(
+            root.path().join("users.py"),
+            "class User:\n    pass\n\ndef find_user(name):\n    \"\"\"Finds a user.\"\"\"\n    return name\n",
+        )?;
+        std::fs::write(
+            root.path().join("orders.py"),
+            "def place_order():\n    pass\n",
+        )?;
+        let files = vec![String::from("orders.py"), String::from("users.py")];
+
+        let index = SymbolIndex::load_from(root.path(), index_path.clone(), ast_grep.clone());
+        index.refresh(&files).await;
+        let names = |symbols: Vec<Symbol>| {
+            symbols
+                .into_iter()
+                .map(|symbol| (symbol.range.path, symbol.kind, symbol.name))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(index.query(Some("USER"), None).await.unwrap()),
+            vec![
+                ("users.py".into(), "class".into(), "User".into()),
+                ("users.py".into(), "function".into(), "find_user".into()),
+            ]
+        );
+        assert_eq!(
+            names(index.query(None, Some("function")).await.unwrap()),
+            vec![
+                ("orders.py".into(), "function".into(), "place_order".into()),
+                ("users.py".into(), "function".into(), "find_user".into()),
+            ]
+        );
+
+        // Documentation is left out of the index
+        assert!(index
+            .query(Some("find_user"), None)
+            .await
+            .unwrap()
+            .iter()
+            .all(|symbol| symbol.documentation.is_none()));
+        assert!(!std::fs::read_to_string(&index_path)?.contains("Finds a user"));
+
+        // A new instance picks up the saved symbols, but isn't queried before it is refreshed
+        std::fs::remove_file(root.path().join("orders.py"))?;
+        let reloaded = SymbolIndex::load_from(root.path(), index_path, ast_grep);
+        assert_eq!(reloaded.files.read().await.len(), 2);
+        assert!(reloaded.query(None, None).await.is_none());
+
+        // Files that are gone are dropped on refresh
+        reloaded.refresh(&files[1..]).await;
+        assert_eq!(
+            names(reloaded.query(None, Some("function")).await.unwrap()),
+            vec![("users.py".into(), "function".into(), "find_user".into())]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_index_update() -> Result<(), Box<dyn std::error::Error>> {
+        let root = tempfile::tempdir()?;
+        let state = tempfile::tempdir()?;
+        let ast_grep = Arc::new(AstGrepClient::new(&LsproxyConfig::default(), &[])?);
+        let index = SymbolIndex::load_from(root.path(), state.path().join("index.json"), ast_grep);
+        let include = vec![String::from("**/*.py")];
+        let exclude = vec![String::from("**/node_modules")];
+        index.refresh(&[]).await;
+
+        // Ignored by git like the files the language servers are given
+        std::fs::create_dir_all(root.path().join(".git"))?;
+        std::fs::write(root.path().join(".gitignore"), "generated/\n")?;
+        std::fs::create_dir_all(root.path().join("src/node_modules"))?;
+        std::fs::create_dir_all(root.path().join("generated"))?;
+        std::fs::write(root.path().join("src/app.py"), "def run():\n    pass\n")?;
+        std::fs::write(
+            root.path().join("src/node_modules/lib.py"),
+            "def lib():\n    pass\n",
+        )?;
+        std::fs::write(
+            root.path().join("generated/models.py"),
+            "def model():\n    pass\n",
+        )?;
+        std::fs::write(root.path().join("notes.txt"), "def notes():\n")?;
+        let paths: Vec<PathBuf> = [
+            "generated/models.py",
+            "notes.txt",
+            "src/app.py",
+            "src/node_modules/lib.py",
+        ]
+        .iter()
+        .map(|file| root.path().join(file))
+        .collect();
+        index.update(&paths, &include, &exclude).await;
+        let indexed: Vec<String> = index
+            .query(None, None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|symbol| symbol.name)
+            .collect();
+        assert_eq!(indexed, vec!["run"]);
+
+        std::fs::remove_dir_all(root.path().join("src"))?;
+        index
+            .update(&[root.path().join("src")], &include, &exclude)
+            .await;
+        assert!(index.query(None, None).await.unwrap().is_empty());
+        Ok(())
+    }
+}
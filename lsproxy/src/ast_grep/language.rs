@@ -0,0 +1,270 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::path::Path;
+
+use ast_grep_core::matcher::{Pattern, PatternBuilder, PatternError, PatternNode};
+use ast_grep_core::meta_var::MetaVariable;
+use ast_grep_core::tree_sitter::{StrDoc, TSLanguage, TSRange};
+use ast_grep_core::{AstGrep, Node};
+use ast_grep_language::{Language, LanguageExt, SupportLang};
+use serde::{Deserialize, Deserializer};
+
+use crate::utils::workspace_documents::PERL_EXTENSIONS;
+
+/// A language ast-grep rules can be written for, with its tree-sitter grammar: the languages
+/// bundled with ast-grep, and the grammars lsproxy adds to them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SgLanguage {
+    Builtin(SupportLang),
+    Perl,
+}
+
+/// Parses the language names and aliases ast-grep accepts, case insensitively.
+pub fn language_from_name(name: &str) -> Option<SgLanguage> {
+    if name.eq_ignore_ascii_case("perl") || name.eq_ignore_ascii_case("pl") {
+        return Some(SgLanguage::Perl);
+    }
+    name.parse().ok().map(SgLanguage::Builtin)
+}
+
+/// The language of `path` from its extension, with ast-grep's default extensions.
+pub fn language_from_extension(path: &Path) -> Option<SgLanguage> {
+    SgLanguage::from_path(path)
+}
+
+impl fmt::Display for SgLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Builtin(language) => language.fmt(f),
+            Self::Perl => write!(f, "Perl"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SgLanguage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        language_from_name(&name)
+            .ok_or_else(|| serde::de::Error::custom(format!("{} is not supported!", name)))
+    }
+}
+
+/// Calls `$method` on the language implementing `SgLanguage`.
+macro_rules! dispatch {
+    ($language:expr, $method:ident($($arg:expr),*)) => {
+        match $language {
+            SgLanguage::Builtin(language) => language.$method($($arg),*),
+            SgLanguage::Perl => Perl.$method($($arg),*),
+        }
+    };
+}
+
+impl Language for SgLanguage {
+    fn kind_to_id(&self, kind: &str) -> u16 {
+        dispatch!(self, kind_to_id(kind))
+    }
+
+    fn field_to_id(&self, field: &str) -> Option<u16> {
+        dispatch!(self, field_to_id(field))
+    }
+
+    fn meta_var_char(&self) -> char {
+        dispatch!(self, meta_var_char())
+    }
+
+    fn expando_char(&self) -> char {
+        dispatch!(self, expando_char())
+    }
+
+    fn extract_meta_var(&self, source: &str) -> Option<MetaVariable> {
+        dispatch!(self, extract_meta_var(source))
+    }
+
+    fn pre_process_pattern<'q>(&self, query: &'q str) -> Cow<'q, str> {
+        dispatch!(self, pre_process_pattern(query))
+    }
+
+    fn build_pattern(&self, builder: &PatternBuilder) -> Result<Pattern, PatternError> {
+        match self {
+            SgLanguage::Builtin(_) => builder.build(|source| StrDoc::try_new(source, *self)),
+            SgLanguage::Perl => Perl.build_pattern(builder),
+        }
+    }
+
+    fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        if extension.is_some_and(|ext| PERL_EXTENSIONS.contains(&ext)) {
+            return Some(Self::Perl);
+        }
+        SupportLang::from_path(path).map(Self::Builtin)
+    }
+}
+
+impl LanguageExt for SgLanguage {
+    fn get_ts_language(&self) -> TSLanguage {
+        dispatch!(self, get_ts_language())
+    }
+
+    fn injectable_languages(&self) -> Option<&'static [&'static str]> {
+        dispatch!(self, injectable_languages())
+    }
+
+    fn extract_injections<L: LanguageExt>(
+        &self,
+        root: Node<StrDoc<L>>,
+    ) -> Vec<(String, Vec<TSRange>)> {
+        dispatch!(self, extract_injections(root))
+    }
+}
+
+/// The Perl grammar of tree-sitter-perl.
+///
+/// Perl variables start with `$` like meta-variables do, so patterns are parsed with the
+/// meta-variables written as identifiers, e.g. `$NAME` as `_NAME`. Variables named like
+/// meta-variables, e.g. `$VERSION`, `$ENV` or `$_`, are written `\$VERSION` to match them.
+#[derive(Clone, Copy)]
+struct Perl;
+
+impl Language for Perl {
+    fn kind_to_id(&self, kind: &str) -> u16 {
+        self.get_ts_language().id_for_node_kind(kind, true)
+    }
+
+    fn field_to_id(&self, field: &str) -> Option<u16> {
+        self.get_ts_language()
+            .field_id_for_name(field)
+            .map(|id| id.get())
+    }
+
+    fn expando_char(&self) -> char {
+        '_'
+    }
+
+    /// Replaces the `$` of `$A`, `$$A`, `$$$A` and `$$$` with the expando character, the `$` of
+    /// Perl variables such as `$self` are kept. `\$A` is the Perl variable `$A`, the backslash
+    /// is dropped, so `\\$A` is a reference to it.
+    fn pre_process_pattern<'q>(&self, query: &'q str) -> Cow<'q, str> {
+        let mut processed = String::with_capacity(query.len());
+        let mut dollars = 0;
+        let mut chars = query.char_indices();
+        while let Some((i, c)) = chars.next() {
+            if c == '$' {
+                dollars += 1;
+                continue;
+            }
+            let meta_variable = is_meta_variable_start(c) || dollars == 3;
+            let sigil = if meta_variable {
+                self.expando_char()
+            } else {
+                '$'
+            };
+            processed.extend(std::iter::repeat_n(sigil, dollars));
+            dollars = 0;
+            let escaped = query[i + 1..]
+                .strip_prefix('$')
+                .and_then(|name| name.chars().next())
+                .is_some_and(is_meta_variable_start);
+            if c == '\\' && escaped {
+                chars.next();
+                processed.push('$');
+            } else {
+                processed.push(c);
+            }
+        }
+        let sigil = if dollars == 3 {
+            self.expando_char()
+        } else {
+            '$'
+        };
+        processed.extend(std::iter::repeat_n(sigil, dollars));
+        Cow::Owned(processed)
+    }
+
+    fn build_pattern(&self, builder: &PatternBuilder) -> Result<Pattern, PatternError> {
+        let mut source = String::new();
+        let mut pattern = builder.build(|query| {
+            source = query.to_string();
+            StrDoc::try_new(query, *self)
+        })?;
+        let Ok(document) = AstGrep::try_new(&source, *self) else {
+            return Ok(pattern);
+        };
+        // ast-grep goes down to the last node with a single child, which can be past the nodes
+        // with hidden text, e.g. down to the `$` of `$self`
+        let mut single = document.root();
+        while single.children().len() == 1 && !has_hidden_text(&single) {
+            single = single.child(0).expect("single child");
+        }
+        let node =
+            if has_hidden_text(&single) && !matches!(pattern.node, PatternNode::Internal { .. }) {
+                pattern = Pattern::from(single.clone());
+                Some(single)
+            } else {
+                let root = document.root();
+                let node = root.dfs().find(|node| is_pattern_of(&pattern.node, node));
+                node
+            };
+        if let Some(node) = node {
+            match_hidden_text(&mut pattern.node, &node);
+        }
+        Ok(pattern)
+    }
+}
+
+/// Whether `pattern` was converted from `node`.
+fn is_pattern_of(pattern: &PatternNode, node: &Node<StrDoc<Perl>>) -> bool {
+    match pattern {
+        PatternNode::Internal { kind_id, children } => {
+            node.kind_id() == *kind_id
+                && node.children().filter(|child| !child.is_missing()).count() == children.len()
+        }
+        _ => false,
+    }
+}
+
+/// Whether part of the text of `node` is in none of its children, e.g. the name of the
+/// `scalar_variable` `$self` is only in its text.
+fn has_hidden_text(node: &Node<StrDoc<Perl>>) -> bool {
+    let visible = |text: &str| text.chars().filter(|c| !c.is_whitespace()).count();
+    let mut children = node
+        .children()
+        .filter(|child| !child.is_missing())
+        .peekable();
+    children.peek().is_some()
+        && children
+            .map(|child| (!child.is_named()).then(|| visible(&child.text())))
+            .sum::<Option<usize>>()
+            .is_some_and(|children_text| children_text < visible(&node.text()))
+}
+
+/// Matches the nodes of `pattern` with hidden text by their text, they would match any node of
+/// their kind otherwise.
+fn match_hidden_text(pattern: &mut PatternNode, node: &Node<StrDoc<Perl>>) {
+    let PatternNode::Internal { kind_id, children } = pattern else {
+        return;
+    };
+    if has_hidden_text(node) {
+        *pattern = PatternNode::Terminal {
+            text: node.text().to_string(),
+            is_named: node.is_named(),
+            kind_id: *kind_id,
+        };
+        return;
+    }
+    let node_children = node.children().filter(|child| !child.is_missing());
+    for (child_pattern, child) in children.iter_mut().zip(node_children) {
+        match_hidden_text(child_pattern, &child);
+    }
+}
+
+/// Whether a `$` followed by `c` starts a meta-variable, as in `$A` or `$_`.
+fn is_meta_variable_start(c: char) -> bool {
+    c.is_ascii_uppercase() || c == '_'
+}
+
+impl LanguageExt for Perl {
+    fn get_ts_language(&self) -> TSLanguage {
+        tree_sitter_perl::LANGUAGE.into()
+    }
+}
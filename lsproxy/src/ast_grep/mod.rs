@@ -1,2 +1,6 @@
 pub(crate) mod client;
+pub(crate) mod document;
+pub(crate) mod documentation;
+pub(crate) mod index;
+pub(crate) mod language;
 pub(crate) mod types;
@@ -1,13 +1,17 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
+use similar::TextDiff;
 
 use crate::{
-    api_types::{FilePosition, FileRange, Position, Symbol},
+    api_types::{
+        qualified_name, FilePosition, FileRange, FileRewrite, Position, StructuralMatch, Symbol,
+    },
     utils::file_utils::absolute_path_to_relative_path_string,
 };
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AstGrepMatch {
     pub text: String,
@@ -19,15 +23,86 @@ pub struct AstGrepMatch {
     pub meta_variables: MetaVariables,
     pub rule_id: String,
     pub labels: Vec<Label>,
+    /// The names of the symbols whose definition contains this one, outermost first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub containers: Vec<String>,
+    /// The decorators of the definition, without the `@`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub decorators: Vec<String>,
+    /// The docstring or doc comment of the definition.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<String>,
 }
 
 impl AstGrepMatch {
     pub fn get_source_code(&self) -> String {
         self.meta_variables.single.context.text.clone()
     }
+
+    /// The symbol of the match, in the file at `path` relative to the workspace.
+    pub fn into_symbol(self, path: String) -> Symbol {
+        let name = self.meta_variables.single.name.text.clone();
+        Symbol {
+            qualified_name: qualified_name(&path, &self.containers, &name),
+            container_name: self.containers.last().cloned(),
+            name,
+            kind: self.rule_id.clone(),
+            decorators: self.decorators.clone(),
+            documentation: self.documentation.clone(),
+            identifier_position: FilePosition {
+                path: path.clone(),
+                position: Position {
+                    line: self.range.start.line as u32,
+                    character: self.range.start.column as u32,
+                },
+                cell: None,
+            },
+            range: FileRange {
+                path: path.clone(),
+                start: Position {
+                    line: self.meta_variables.single.context.range.start.line as u32,
+                    // character: ast_match
+                    //     .meta_variables
+                    //     .single
+                    //     .context
+                    //     .range
+                    //     .start
+                    //     .column as u32,
+                    character: 0, // TODO: this is not technically true, we're returning the whole line for consistency
+                },
+                end: Position {
+                    line: self.meta_variables.single.context.range.end.line as u32,
+                    character: self.meta_variables.single.context.range.end.column as u32,
+                },
+                cell: None,
+            },
+        }
+    }
+}
+
+/// A match of a search rule, with the nodes captured by its meta-variables.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleMatch {
+    pub text: String,
+    pub range: AstGrepRange,
+    pub file: String,
+    pub language: String,
+    /// A `$$$` meta-variable spans the nodes it matched, empty ones are left out.
+    pub meta_variables: BTreeMap<String, MetaVariable>,
+}
+
+/// The rewrite of a file, with the source before and after it.
+#[derive(Debug, Clone)]
+pub struct FileEdit {
+    pub file: String,
+    pub source: String,
+    pub rewritten: String,
+    /// The number of replaced matches.
+    pub matches: usize,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AstGrepRange {
     pub byte_offset: ByteOffset,
@@ -35,35 +110,35 @@ pub struct AstGrepRange {
     pub end: AstGrepPosition,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct ByteOffset {
     pub start: usize,
     pub end: usize,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AstGrepPosition {
     pub line: usize,
     pub column: usize,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct CharCount {
     pub leading: usize,
     pub trailing: usize,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct MetaVariables {
     pub single: SingleVariable,
     pub multi: MultiVariables,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SingleVariable {
     #[serde(rename = "NAME")]
     pub name: MetaVariable,
@@ -71,20 +146,20 @@ pub struct SingleVariable {
     pub context: MetaVariable,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct MultiVariables {
     pub secondary: Vec<MetaVariable>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct MetaVariable {
     pub text: String,
     pub range: AstGrepRange,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Label {
     pub text: String,
@@ -94,34 +169,53 @@ pub struct Label {
 impl From<AstGrepMatch> for Symbol {
     fn from(ast_match: AstGrepMatch) -> Self {
         let path = absolute_path_to_relative_path_string(&PathBuf::from(ast_match.file.clone()));
-        Symbol {
-            name: ast_match.meta_variables.single.name.text.clone(),
-            kind: ast_match.rule_id.clone(),
-            identifier_position: FilePosition {
-                path: path.clone(),
-                position: Position {
-                    line: ast_match.range.start.line as u32,
-                    character: ast_match.range.start.column as u32,
-                },
+        ast_match.into_symbol(path)
+    }
+}
+
+impl AstGrepRange {
+    fn file_range(&self, path: &str) -> FileRange {
+        FileRange {
+            path: path.to_string(),
+            start: Position {
+                line: self.start.line as u32,
+                character: self.start.column as u32,
             },
-            range: FileRange {
-                path: path.clone(),
-                start: Position {
-                    line: ast_match.meta_variables.single.context.range.start.line as u32,
-                    // character: ast_match
-                    //     .meta_variables
-                    //     .single
-                    //     .context
-                    //     .range
-                    //     .start
-                    //     .column as u32,
-                    character: 0, // TODO: this is not technically true, we're returning the whole line for consistency
-                },
-                end: Position {
-                    line: ast_match.meta_variables.single.context.range.end.line as u32,
-                    character: ast_match.meta_variables.single.context.range.end.column as u32,
-                },
+            end: Position {
+                line: self.end.line as u32,
+                character: self.end.column as u32,
             },
+            cell: None,
+        }
+    }
+}
+
+impl From<RuleMatch> for StructuralMatch {
+    fn from(rule_match: RuleMatch) -> Self {
+        let path = absolute_path_to_relative_path_string(&PathBuf::from(&rule_match.file));
+        StructuralMatch {
+            range: rule_match.range.file_range(&path),
+            text: rule_match.text,
+            meta_variables: rule_match
+                .meta_variables
+                .iter()
+                .map(|(name, variable)| (name.clone(), variable.range.file_range(&path)))
+                .collect(),
+        }
+    }
+}
+
+impl From<FileEdit> for FileRewrite {
+    fn from(edit: FileEdit) -> Self {
+        let path = absolute_path_to_relative_path_string(&PathBuf::from(&edit.file));
+        let diff = TextDiff::from_lines(&edit.source, &edit.rewritten)
+            .unified_diff()
+            .header(&format!("a/{}", path), &format!("b/{}", path))
+            .to_string();
+        FileRewrite {
+            path,
+            matches: edit.matches,
+            diff,
         }
     }
 }
@@ -0,0 +1,504 @@
+use log::{debug, warn};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::ast_grep::language::language_from_name;
+use crate::utils::language_detection::{language, LANGUAGE_IDS};
+use crate::utils::workspace_documents::DidOpenConfiguration;
+
+/// Environment variable pointing at an explicit configuration file.
+pub const CONFIG_PATH_ENV_VAR: &str = "LSPROXY_CONFIG";
+
+/// Environment variable overriding the directory lsproxy writes derived files to.
+pub const STATE_DIR_ENV_VAR: &str = "LSPROXY_STATE_DIR";
+
+/// Environment variable allowing the workspace configuration file to add language servers.
+pub const TRUST_WORKSPACE_CONFIG_ENV_VAR: &str = "LSPROXY_TRUST_WORKSPACE_CONFIG";
+
+/// Configuration files looked up in the workspace root when `LSPROXY_CONFIG` is unset.
+pub const WORKSPACE_CONFIG_FILES: &[&str] = &[".lsproxy/config.toml", ".lsproxy/config.json"];
+
+/// ast-grep rules of the workspace, loaded along with the configured `rule_dirs`.
+pub const WORKSPACE_RULES_DIR: &str = ".lsproxy/rules";
+
+/// Operator configuration for lsproxy, loaded from a TOML or JSON file.
+///
+/// Every field is optional, an empty file is equivalent to the defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LsproxyConfig {
+    /// Additional language servers, started alongside the built-in ones.
+    pub language_servers: Vec<LanguageServerConfig>,
+    pub python: PythonConfig,
+    pub readiness: ReadinessConfig,
+    /// Extensions, without the leading dot, mapped to a `languageId` like `cpp` or `php`.
+    ///
+    /// e.g. in `.lsproxy/config.toml`:
+    /// ```toml
+    /// [extensions]
+    /// h = "cpp"
+    /// inc = "php"
+    /// ```
+    pub extensions: HashMap<String, String>,
+    pub ast_grep: AstGrepConfig,
+}
+
+/// ast-grep rules added to the built-in ones, e.g. for project-specific symbol kinds.
+///
+/// e.g. in `.lsproxy/config.toml`:
+/// ```toml
+/// [ast_grep]
+/// rule_dirs = ["tools/symbol-rules", "/etc/lsproxy/rules"]
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AstGrepConfig {
+    /// Directories searched for `.yml` and `.yaml` rule files, relative to the workspace root
+    /// unless absolute.
+    pub rule_dirs: Vec<PathBuf>,
+}
+
+impl AstGrepConfig {
+    /// The configured rule directories, then `.lsproxy/rules` if the workspace has one.
+    pub fn rule_dirs(&self, root_path: &Path) -> Vec<PathBuf> {
+        let mut rule_dirs: Vec<PathBuf> = self
+            .rule_dirs
+            .iter()
+            .map(|dir| root_path.join(dir))
+            .collect();
+        let workspace_rules = root_path.join(WORKSPACE_RULES_DIR);
+        if workspace_rules.is_dir() && !rule_dirs.contains(&workspace_rules) {
+            rule_dirs.push(workspace_rules);
+        }
+        rule_dirs
+    }
+}
+
+/// How requests wait for language servers that are still indexing.
+///
+/// e.g. in `.lsproxy/config.toml`:
+/// ```toml
+/// [readiness]
+/// timeout_secs = 120
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ReadinessConfig {
+    /// How long a request waits for indexing to finish, 0 fails right away. A server busy for
+    /// that long in a row counts as ready, unless it is 0.
+    pub timeout_secs: u64,
+    /// Sent as `Retry-After` when the wait timed out.
+    pub retry_after_secs: u64,
+    /// How long a server that never reports its indexing counts as busy after it started, and
+    /// how long the progress tokens it creates wait for their work to begin.
+    pub startup_secs: u64,
+}
+
+impl Default for ReadinessConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 30,
+            retry_after_secs: 10,
+            startup_secs: 10,
+        }
+    }
+}
+
+/// Language server used for Python files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PythonBackend {
+    #[default]
+    Jedi,
+    Pyright,
+    BasedPyright,
+}
+
+/// e.g. in `.lsproxy/config.toml`:
+/// ```toml
+/// [python]
+/// backend = "basedpyright"
+///
+/// [python.settings.basedpyright.analysis]
+/// typeCheckingMode = "off"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PythonConfig {
+    pub backend: PythonBackend,
+    /// Sent as `initializationOptions` in the `initialize` request.
+    pub initialization_options: Option<Value>,
+    /// Sent with `workspace/didChangeConfiguration` once the server is initialized.
+    /// Only pyright and basedpyright read settings this way.
+    pub settings: Option<Value>,
+}
+
+/// A language server that is not built into lsproxy.
+///
+/// e.g. in `.lsproxy/config.toml`:
+/// ```toml
+/// [[language_servers]]
+/// name = "lua"
+/// command = "lua-language-server"
+/// extensions = ["lua"]
+/// root_files = [".luarc.json"]
+/// did_open = "lazy"
+/// ast_grep_language = "lua"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct LanguageServerConfig {
+    /// Unique name of the server, also the default `languageId`.
+    pub name: String,
+    /// Executable to spawn, it must speak LSP over stdio.
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// File extensions handled by the server, without the leading dot.
+    pub extensions: Vec<String>,
+    /// Files that mark the root of a project, used to find workspace folders.
+    #[serde(default)]
+    pub root_files: Vec<String>,
+    /// Whether documents have to be opened with `textDocument/didOpen` before querying them.
+    #[serde(default = "default_did_open")]
+    pub did_open: DidOpenConfiguration,
+    /// Sent as `initializationOptions` in the `initialize` request.
+    #[serde(default)]
+    pub initialization_options: Option<Value>,
+    /// `languageId` sent with `textDocument/didOpen`, defaults to `name`.
+    #[serde(default)]
+    pub language_id: Option<String>,
+    /// ast-grep language used to extract symbols from the files, e.g. `kotlin` or `lua`.
+    /// Any language name or alias ast-grep supports.
+    #[serde(default)]
+    pub ast_grep_language: Option<String>,
+}
+
+/// Directory for files generated by lsproxy, kept outside of the mounted workspace.
+pub fn state_dir() -> PathBuf {
+    std::env::var(STATE_DIR_ENV_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| std::env::temp_dir().join("lsproxy"))
+}
+
+fn default_did_open() -> DidOpenConfiguration {
+    DidOpenConfiguration::Lazy
+}
+
+impl LanguageServerConfig {
+    pub fn file_patterns(&self) -> Vec<String> {
+        self.extensions
+            .iter()
+            .map(|ext| format!("**/*.{}", ext))
+            .collect()
+    }
+
+    pub fn handles_file(&self, file_path: &str) -> bool {
+        Path::new(file_path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.extensions.iter().any(|e| e == ext))
+    }
+
+    pub fn language_id(&self) -> String {
+        self.language_id
+            .clone()
+            .unwrap_or_else(|| self.name.clone())
+    }
+}
+
+impl LsproxyConfig {
+    /// Loads the configuration for the workspace at `root_path`.
+    ///
+    /// `LSPROXY_CONFIG` takes precedence over the files in `WORKSPACE_CONFIG_FILES`.
+    /// Returns the defaults when no configuration file exists.
+    pub fn load(root_path: &str) -> Result<Self, Box<dyn Error>> {
+        if let Ok(path) = std::env::var(CONFIG_PATH_ENV_VAR) {
+            debug!("Loading configuration from {}", path);
+            return Self::from_file(Path::new(&path));
+        }
+        let trusted = std::env::var(TRUST_WORKSPACE_CONFIG_ENV_VAR)
+            .is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true"));
+        Self::load_workspace_config(root_path, trusted)
+    }
+
+    /// Loads the configuration file of the workspace at `root_path`.
+    ///
+    /// The commands of its language servers would run whatever the mounted workspace asks for,
+    /// so they are ignored unless the operator opted in with `trusted`.
+    fn load_workspace_config(root_path: &str, trusted: bool) -> Result<Self, Box<dyn Error>> {
+        let Some(path) = WORKSPACE_CONFIG_FILES
+            .iter()
+            .map(|file| Path::new(root_path).join(file))
+            .find(|path| path.is_file())
+        else {
+            return Ok(Self::default());
+        };
+        debug!("Loading configuration from {:?}", path);
+        let mut config = Self::from_file(&path)?;
+        if !trusted && !config.language_servers.is_empty() {
+            warn!(
+                "Ignoring the language servers of {}, set {}=1 to start them",
+                path.display(),
+                TRUST_WORKSPACE_CONFIG_ENV_VAR
+            );
+            config.language_servers.clear();
+        }
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+        let config: Self = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&content)
+                .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?,
+            Some("json") => serde_json::from_str(&content)
+                .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?,
+            _ => {
+                return Err(format!(
+                    "Unsupported config file format: {}, expected .toml or .json",
+                    path.display()
+                )
+                .into())
+            }
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Returns the configured language server handling `file_path`, if any.
+    pub fn language_server_for_file(&self, file_path: &str) -> Option<&LanguageServerConfig> {
+        self.language_servers
+            .iter()
+            .find(|server| server.handles_file(file_path))
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        for (i, server) in self.language_servers.iter().enumerate() {
+            if server.extensions.is_empty() {
+                return Err(format!("Language server '{}' has no extensions", server.name).into());
+            }
+            if self.language_servers[..i]
+                .iter()
+                .any(|other| other.name == server.name)
+            {
+                return Err(format!("Duplicate language server name '{}'", server.name).into());
+            }
+            if let Some(ast_grep_language) = &server.ast_grep_language {
+                if language_from_name(ast_grep_language).is_none() {
+                    return Err(format!(
+                        "Unknown ast-grep language '{}' for language server '{}'",
+                        ast_grep_language, server.name
+                    )
+                    .into());
+                }
+            }
+        }
+        for (extension, id) in &self.extensions {
+            if language(id).is_none() {
+                let ids: Vec<&str> = LANGUAGE_IDS.iter().map(|language| language.id).collect();
+                return Err(format!(
+                    "Unknown language '{}' for extension '{}', expected one of: {}",
+                    id,
+                    extension,
+                    ids.join(", ")
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_without_config_file() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let config = LsproxyConfig::load(dir.path().to_str().unwrap())?;
+        assert!(config.language_servers.is_empty());
+        assert_eq!(config.python.backend, PythonBackend::Jedi);
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_toml_language_servers() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        fs::create_dir(dir.path().join(".lsproxy"))?;
+        fs::write(
+            dir.path().join(".lsproxy/config.toml"),
+            r#"
+[[language_servers]]
+name = "lua"
+command = "lua-language-server"
+extensions = ["lua"]
+did_open = "none"
+ast_grep_language = "lua"
+
+[language_servers.initialization_options]
+diagnostics = { enable = false }
+"#,
+        )?;
+
+        let config = LsproxyConfig::load_workspace_config(dir.path().to_str().unwrap(), true)?;
+        let server = config
+            .language_server_for_file("/workspace/src/init.lua")
+            .ok_or("lua server not found")?;
+        assert_eq!(server.command, "lua-language-server");
+        assert!(server.args.is_empty());
+        assert_eq!(server.did_open, DidOpenConfiguration::None);
+        assert_eq!(server.language_id(), "lua");
+        assert_eq!(server.file_patterns(), vec!["**/*.lua"]);
+        assert_eq!(server.ast_grep_language.as_deref(), Some("lua"));
+        assert_eq!(
+            server.initialization_options,
+            Some(serde_json::json!({"diagnostics": {"enable": false}}))
+        );
+        assert!(config.language_server_for_file("main.py").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ignore_untrusted_workspace_language_servers() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        fs::create_dir(dir.path().join(".lsproxy"))?;
+        fs::write(
+            dir.path().join(".lsproxy/config.toml"),
+            "[[language_servers]]\nname = \"lua\"\ncommand = \"lua-language-server\"\nextensions = [\"lua\"]\n\n[python]\nbackend = \"pyright\"\n",
+        )?;
+
+        let config = LsproxyConfig::load_workspace_config(dir.path().to_str().unwrap(), false)?;
+        assert!(config.language_servers.is_empty());
+        assert_eq!(config.python.backend, PythonBackend::Pyright);
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_json_language_servers() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("lsproxy.json");
+        fs::write(
+            &path,
+            r#"{"language_servers": [{
+                "name": "kotlin",
+                "command": "kotlin-language-server",
+                "extensions": ["kt", "kts"],
+                "language_id": "kotlin",
+                "ast_grep_language": "kotlin"
+            }]}"#,
+        )?;
+
+        let config = LsproxyConfig::from_file(&path)?;
+        let server = config
+            .language_server_for_file("build.gradle.kts")
+            .ok_or("kotlin server not found")?;
+        assert_eq!(server.did_open, DidOpenConfiguration::Lazy);
+        assert_eq!(server.ast_grep_language.as_deref(), Some("kotlin"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_python_backend() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("lsproxy.toml");
+        fs::write(
+            &path,
+            r#"
+[python]
+backend = "basedpyright"
+
+[python.settings.basedpyright.analysis]
+typeCheckingMode = "off"
+"#,
+        )?;
+
+        let config = LsproxyConfig::from_file(&path)?;
+        assert_eq!(config.python.backend, PythonBackend::BasedPyright);
+        assert_eq!(
+            config.python.settings,
+            Some(serde_json::json!({"basedpyright": {"analysis": {"typeCheckingMode": "off"}}}))
+        );
+        assert!(config.python.initialization_options.is_none());
+        assert_eq!(config.readiness.timeout_secs, 30);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_duplicate_names() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("lsproxy.json");
+        fs::write(
+            &path,
+            r#"{"language_servers": [
+                {"name": "lua", "command": "a", "extensions": ["lua"]},
+                {"name": "lua", "command": "b", "extensions": ["luau"]}
+            ]}"#,
+        )?;
+        assert!(LsproxyConfig::from_file(&path).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_unknown_ast_grep_language() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("lsproxy.toml");
+        fs::write(
+            &path,
+            "[[language_servers]]\nname = \"pascal\"\ncommand = \"pasls\"\nextensions = [\"pas\"]\nast_grep_language = \"pascal\"\n",
+        )?;
+        let error = LsproxyConfig::from_file(&path).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Unknown ast-grep language 'pascal'"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_extensions() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("lsproxy.toml");
+        fs::write(&path, "[extensions]\nh = \"cpp\"\ninc = \"php\"\n")?;
+        let config = LsproxyConfig::from_file(&path)?;
+        assert_eq!(
+            config.extensions.get("inc").map(String::as_str),
+            Some("php")
+        );
+
+        fs::write(&path, "[extensions]\ninc = \"pascal\"\n")?;
+        let error = LsproxyConfig::from_file(&path).unwrap_err();
+        assert!(error.to_string().contains("Unknown language 'pascal'"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_rule_dirs() -> Result<(), Box<dyn Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("lsproxy.toml");
+        fs::write(
+            &path,
+            "[ast_grep]\nrule_dirs = [\"rules\", \"/etc/lsproxy/rules\"]\n",
+        )?;
+        let config = LsproxyConfig::from_file(&path)?;
+        assert_eq!(
+            config.ast_grep.rule_dirs(dir.path()),
+            vec![
+                dir.path().join("rules"),
+                PathBuf::from("/etc/lsproxy/rules")
+            ]
+        );
+
+        fs::create_dir_all(dir.path().join(WORKSPACE_RULES_DIR))?;
+        assert_eq!(
+            AstGrepConfig::default().rule_dirs(dir.path()),
+            vec![dir.path().join(WORKSPACE_RULES_DIR)]
+        );
+        Ok(())
+    }
+}
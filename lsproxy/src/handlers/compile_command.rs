@@ -0,0 +1,98 @@
+use actix_web::web::{Data, Query};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{CompileCommandRequest, CompileCommandResponse, ErrorResponse};
+use crate::lsp::manager::LspManagerError;
+use crate::AppState;
+
+/// Get the compile command of a C or C++ file
+///
+/// Returns the command clangd uses to parse the file, which decides include paths, macros and
+/// the language standard. Useful to debug wrong or missing definitions in C and C++ projects.
+///
+/// When the workspace has neither a `compile_commands.json` nor a `compile_flags.txt`,
+/// lsproxy generates the commands from the CMake and Makefiles it finds.
+#[utoipa::path(
+    get,
+    path = "/workspace/compile-command",
+    tag = "workspace",
+    params(CompileCommandRequest),
+    responses(
+        (status = 200, description = "Compile command retrieved successfully", body = CompileCommandResponse),
+        (status = 400, description = "Bad request"),
+        (status = 404, description = "No compile command for the file"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn compile_command(
+    data: Data<AppState>,
+    info: Query<CompileCommandRequest>,
+) -> HttpResponse {
+    info!(
+        "Received compile command request for file: {}",
+        info.file_path
+    );
+    let manager = match data.manager.lock() {
+        Ok(manager) => manager.clone(),
+        Err(e) => {
+            error!("Failed to acquire lock on LSP manager: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Internal server error".to_string(),
+            });
+        }
+    };
+    match manager.compile_command(&info.file_path).await {
+        Ok(Some(command)) => HttpResponse::Ok().json(command),
+        Ok(None) => HttpResponse::NotFound().json(ErrorResponse {
+            error: format!("No compile command for {}", info.file_path),
+        }),
+        Err(e) => {
+            error!("Failed to get compile command: {}", e);
+            match e {
+                LspManagerError::FileNotFound(_) | LspManagerError::UnsupportedFileType(_) => {
+                    HttpResponse::BadRequest().json(ErrorResponse {
+                        error: e.to_string(),
+                    })
+                }
+                _ => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::initialize_app_state;
+    use crate::test_utils::{c_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_c_compile_command() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&c_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let mock_request = Query(CompileCommandRequest {
+            file_path: String::from("map.c"),
+        });
+
+        let response = compile_command(state, mock_request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let command: CompileCommandResponse = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(command.source, "generated");
+        assert_eq!(
+            command.command,
+            format!("/usr/bin/cc -c {}/map.c", c_sample_path())
+        );
+        Ok(())
+    }
+}
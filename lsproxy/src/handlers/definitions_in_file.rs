@@ -3,12 +3,17 @@ use actix_web::HttpResponse;
 use log::{error, info};
 
 use crate::api_types::{ErrorResponse, FileSymbolsRequest, Symbol};
+use crate::handlers::not_ready_response;
+use crate::lsp::manager::LspManagerError;
+use crate::utils::notebook::NotebookPositions;
 use crate::AppState;
 
 /// Get symbols in a specific file (uses ast-grep)
 ///
 /// Returns a list of symbols (functions, classes, variables, etc.) defined in the specified file.
 ///
+/// Languages without ast-grep rules get the document symbols of their language server.
+///
 /// Only the variabels defined at the file level are included.
 ///
 /// The returned positions point to the start of the symbol's identifier.
@@ -29,7 +34,8 @@ use crate::AppState;
     responses(
         (status = 200, description = "Symbols retrieved successfully", body = Vec<Symbol>),
         (status = 400, description = "Bad request"),
-        (status = 500, description = "Internal server error")
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Language server is still indexing, retry after the `Retry-After` seconds")
     )
 )]
 pub async fn definitions_in_file(
@@ -41,7 +47,7 @@ pub async fn definitions_in_file(
         info.file_path
     );
     let manager = match data.manager.lock() {
-        Ok(guard) => guard,
+        Ok(manager) => manager.clone(),
         Err(e) => {
             error!("Failed to acquire lock on LSP manager: {}", e);
             return HttpResponse::InternalServerError().json(ErrorResponse {
@@ -49,11 +55,23 @@ pub async fn definitions_in_file(
             });
         }
     };
-    match manager.definitions_in_file_ast_grep(&info.file_path).await {
+    match manager.definitions_in_file(&info.file_path).await {
         Ok(symbols) => {
-            let symbol_response: Vec<Symbol> = symbols.into_iter().map(Symbol::from).collect();
+            let mut notebooks = NotebookPositions::default();
+            let symbol_response: Vec<Symbol> = symbols
+                .into_iter()
+                .map(|mut symbol| {
+                    if !info.include_documentation {
+                        symbol.documentation = None;
+                    }
+                    notebooks.map_position(&mut symbol.identifier_position);
+                    notebooks.map_range(&mut symbol.range);
+                    symbol
+                })
+                .collect();
             HttpResponse::Ok().json(symbol_response)
         }
+        Err(e @ LspManagerError::NotReady(_)) => not_ready_response(&e, manager.retry_after()),
         Err(e) => HttpResponse::BadRequest().json(ErrorResponse {
             error: format!("Couldn't get symbols: {}", e),
         }),
@@ -68,7 +86,7 @@ mod test {
 
     use crate::api_types::{FilePosition, FileRange, Position, Symbol};
     use crate::initialize_app_state;
-    use crate::test_utils::{python_sample_path, TestContext};
+    use crate::test_utils::{jupyter_sample_path, python_sample_path, TestContext};
 
     #[tokio::test]
     async fn test_python_file_symbols() -> Result<(), Box<dyn std::error::Error>> {
@@ -77,6 +95,7 @@ mod test {
 
         let mock_request = Query(FileSymbolsRequest {
             file_path: String::from("main.py"),
+            include_documentation: false,
         });
 
         let response = definitions_in_file(state, mock_request).await;
@@ -96,12 +115,17 @@ mod test {
             Symbol {
                 name: String::from("graph"),
                 kind: String::from("variable"),
+                container_name: None,
+                qualified_name: String::from("main.graph"),
+                decorators: Vec::new(),
+                documentation: None,
                 identifier_position: FilePosition {
                     path: String::from("main.py"),
                     position: Position {
                         line: 5,
                         character: 0,
                     },
+                    cell: None,
                 },
                 range: FileRange {
                     path: String::from("main.py"),
@@ -113,17 +137,23 @@ mod test {
                         line: 5,
                         character: 20,
                     },
+                    cell: None,
                 },
             },
             Symbol {
                 name: String::from("result"),
                 kind: String::from("variable"),
+                container_name: None,
+                qualified_name: String::from("main.result"),
+                decorators: Vec::new(),
+                documentation: None,
                 identifier_position: FilePosition {
                     path: String::from("main.py"),
                     position: Position {
                         line: 6,
                         character: 0,
                     },
+                    cell: None,
                 },
                 range: FileRange {
                     path: String::from("main.py"),
@@ -135,17 +165,23 @@ mod test {
                         line: 6,
                         character: 51,
                     },
+                    cell: None,
                 },
             },
             Symbol {
                 name: String::from("cost"),
                 kind: String::from("variable"),
+                container_name: None,
+                qualified_name: String::from("main.cost"),
+                decorators: Vec::new(),
+                documentation: None,
                 identifier_position: FilePosition {
                     path: String::from("main.py"),
                     position: Position {
                         line: 6,
                         character: 8,
                     },
+                    cell: None,
                 },
                 range: FileRange {
                     path: String::from("main.py"),
@@ -157,6 +193,117 @@ mod test {
                         line: 6,
                         character: 51,
                     },
+                    cell: None,
+                },
+            },
+        ];
+
+        assert_eq!(expected, file_symbols_response);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_notebook_file_symbols() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&jupyter_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let mock_request = Query(FileSymbolsRequest {
+            file_path: String::from("explore.ipynb"),
+            include_documentation: false,
+        });
+
+        let response = definitions_in_file(state, mock_request).await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let file_symbols_response: Vec<Symbol> = serde_json::from_slice(&bytes).unwrap();
+
+        // Lines are relative to the cells, the markdown cell 0 still counts
+        let expected = vec![
+            Symbol {
+                name: String::from("circle"),
+                kind: String::from("variable"),
+                container_name: None,
+                qualified_name: String::from("explore.circle"),
+                decorators: Vec::new(),
+                documentation: None,
+                identifier_position: FilePosition {
+                    path: String::from("explore.ipynb"),
+                    position: Position {
+                        line: 2,
+                        character: 0,
+                    },
+                    cell: Some(1),
+                },
+                range: FileRange {
+                    path: String::from("explore.ipynb"),
+                    start: Position {
+                        line: 2,
+                        character: 0,
+                    },
+                    end: Position {
+                        line: 2,
+                        character: 18,
+                    },
+                    cell: Some(1),
+                },
+            },
+            Symbol {
+                name: String::from("describe"),
+                kind: String::from("function"),
+                container_name: None,
+                qualified_name: String::from("explore.describe"),
+                decorators: Vec::new(),
+                documentation: None,
+                identifier_position: FilePosition {
+                    path: String::from("explore.ipynb"),
+                    position: Position {
+                        line: 0,
+                        character: 4,
+                    },
+                    cell: Some(3),
+                },
+                range: FileRange {
+                    path: String::from("explore.ipynb"),
+                    start: Position {
+                        line: 0,
+                        character: 0,
+                    },
+                    end: Position {
+                        line: 1,
+                        character: 65,
+                    },
+                    cell: Some(3),
+                },
+            },
+            Symbol {
+                name: String::from("description"),
+                kind: String::from("variable"),
+                container_name: None,
+                qualified_name: String::from("explore.description"),
+                decorators: Vec::new(),
+                documentation: None,
+                identifier_position: FilePosition {
+                    path: String::from("explore.ipynb"),
+                    position: Position {
+                        line: 3,
+                        character: 0,
+                    },
+                    cell: Some(3),
+                },
+                range: FileRange {
+                    path: String::from("explore.ipynb"),
+                    start: Position {
+                        line: 3,
+                        character: 0,
+                    },
+                    end: Position {
+                        line: 3,
+                        character: 30,
+                    },
+                    cell: Some(3),
                 },
             },
         ];
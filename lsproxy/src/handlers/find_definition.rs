@@ -1,11 +1,13 @@
 use crate::api_types::{CodeContext, ErrorResponse, FileRange, Position};
 use crate::lsp::manager::{LspManagerError, Manager};
 use crate::utils::file_utils::uri_to_relative_path_string;
+use crate::utils::notebook::NotebookPositions;
 use actix_web::web::{Data, Json};
 use actix_web::HttpResponse;
 use log::{error, info, warn};
 
 use crate::api_types::{DefinitionResponse, GetDefinitionRequest};
+use crate::handlers::not_ready_response;
 use crate::AppState;
 use lsp_types::{GotoDefinitionResponse, Location, Position as LspPosition};
 /// Get the definition of a symbol at a specific position in a file
@@ -35,7 +37,8 @@ use lsp_types::{GotoDefinitionResponse, Location, Position as LspPosition};
     responses(
         (status = 200, description = "Definition retrieved successfully", body = DefinitionResponse),
         (status = 400, description = "Bad request"),
-        (status = 500, description = "Internal server error")
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Language server is still indexing, retry after the `Retry-After` seconds")
     )
 )]
 pub async fn find_definition(
@@ -56,24 +59,44 @@ pub async fn find_definition(
                 error: format!("Failed to lock manager: {}", e),
             })
         })
-        .unwrap();
+        .unwrap()
+        .clone();
 
-    let definitions = manager
+    let mut notebooks = NotebookPositions::default();
+    let line = match notebooks.document_line(
+        &info.position.path,
+        info.position.cell,
+        info.position.position.line,
+    ) {
+        Ok(line) => line,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Invalid position: {}", e),
+            })
+        }
+    };
+
+    let definitions = match manager
         .find_definition(
             &info.position.path,
             LspPosition {
-                line: info.position.position.line,
+                line,
                 character: info.position.position.character,
             },
         )
         .await
-        .map_err(|e| {
+    {
+        Ok(definitions) => definitions,
+        Err(e) => {
             error!("Definition error: {:?}", e);
-            HttpResponse::InternalServerError().json(ErrorResponse {
-                error: format!("Definition retrieval failed: {}", e),
-            })
-        })
-        .unwrap();
+            return match e {
+                LspManagerError::NotReady(_) => not_ready_response(&e, manager.retry_after()),
+                _ => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: format!("Definition retrieval failed: {}", e),
+                }),
+            };
+        }
+    };
 
     let source_code_context = if info.include_source_code {
         match fetch_definition_source_code(&manager, &definitions).await {
@@ -87,11 +110,18 @@ pub async fn find_definition(
         None
     };
 
-    HttpResponse::Ok().json(DefinitionResponse::from((
-        definitions,
-        source_code_context,
-        info.include_raw_response,
-    )))
+    let mut response =
+        DefinitionResponse::from((definitions, source_code_context, info.include_raw_response));
+    response
+        .definitions
+        .iter_mut()
+        .for_each(|definition| notebooks.map_position(definition));
+    if let Some(contexts) = response.source_code_context.as_mut() {
+        contexts
+            .iter_mut()
+            .for_each(|context| notebooks.map_range(&mut context.range));
+    }
+    HttpResponse::Ok().json(response)
 }
 
 async fn fetch_definition_source_code(
@@ -139,6 +169,7 @@ async fn fetch_definition_source_code(
                         line: symbol.range.end.line as u32,
                         character: symbol.range.end.column as u32,
                     },
+                    cell: None,
                 },
                 source_code,
             });
@@ -155,7 +186,7 @@ mod test {
 
     use crate::api_types::{FilePosition, Position};
     use crate::initialize_app_state;
-    use crate::test_utils::{python_sample_path, TestContext};
+    use crate::test_utils::{jupyter_sample_path, python_sample_path, TestContext};
 
     #[tokio::test]
     async fn test_python_definition() -> Result<(), Box<dyn std::error::Error>> {
@@ -169,6 +200,7 @@ mod test {
                     line: 1,
                     character: 18,
                 },
+                cell: None,
             },
             include_source_code: false,
             include_raw_response: false,
@@ -194,6 +226,7 @@ mod test {
                     line: 1,
                     character: 6,
                 },
+                cell: None,
             }],
             source_code_context: None,
         };
@@ -201,4 +234,73 @@ mod test {
         assert_eq!(expected_response, definition_response);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_notebook_definition() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&jupyter_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // `Circle` in `circle = Circle(2)`, defined in a python module
+        let mock_request = Json(GetDefinitionRequest {
+            position: FilePosition {
+                path: String::from("explore.ipynb"),
+                position: Position {
+                    line: 2,
+                    character: 9,
+                },
+                cell: Some(1),
+            },
+            include_source_code: false,
+            include_raw_response: false,
+        });
+        let response = find_definition(state.clone(), mock_request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let definition_response: DefinitionResponse = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            definition_response.definitions,
+            vec![FilePosition {
+                path: String::from("shapes.py"),
+                position: Position {
+                    line: 3,
+                    character: 6,
+                },
+                cell: None,
+            }]
+        );
+
+        // `describe` in `description = describe(circle)`, defined in the same cell
+        let mock_request = Json(GetDefinitionRequest {
+            position: FilePosition {
+                path: String::from("explore.ipynb"),
+                position: Position {
+                    line: 3,
+                    character: 14,
+                },
+                cell: Some(3),
+            },
+            include_source_code: false,
+            include_raw_response: false,
+        });
+        let response = find_definition(state, mock_request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let definition_response: DefinitionResponse = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            definition_response.definitions,
+            vec![FilePosition {
+                path: String::from("explore.ipynb"),
+                position: Position {
+                    line: 0,
+                    character: 4,
+                },
+                cell: Some(3),
+            }]
+        );
+        Ok(())
+    }
 }
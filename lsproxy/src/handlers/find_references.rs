@@ -5,8 +5,10 @@ use lsp_types::{Location, Position as LspPosition, Range};
 
 use crate::api_types::{CodeContext, ErrorResponse, FileRange, Position};
 use crate::api_types::{GetReferencesRequest, ReferencesResponse};
+use crate::handlers::not_ready_response;
 use crate::lsp::manager::{LspManagerError, Manager};
 use crate::utils::file_utils::uri_to_relative_path_string;
+use crate::utils::notebook::NotebookPositions;
 use crate::AppState;
 
 /// Find all references to a symbol
@@ -36,7 +38,8 @@ use crate::AppState;
     responses(
         (status = 200, description = "References retrieved successfully", body = ReferencesResponse),
         (status = 400, description = "Bad request"),
-        (status = 500, description = "Internal server error")
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Language server is still indexing, retry after the `Retry-After` seconds")
     )
 )]
 pub async fn find_references(
@@ -49,13 +52,27 @@ pub async fn find_references(
         info.identifier_position.position.line,
         info.identifier_position.position.character
     );
-    let manager = data.manager.lock().unwrap();
+    let manager = data.manager.lock().unwrap().clone();
+
+    let mut notebooks = NotebookPositions::default();
+    let line = match notebooks.document_line(
+        &info.identifier_position.path,
+        info.identifier_position.cell,
+        info.identifier_position.position.line,
+    ) {
+        Ok(line) => line,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Invalid position: {}", e),
+            })
+        }
+    };
 
     let references_result = manager
         .find_references(
             &info.identifier_position.path,
             LspPosition {
-                line: info.identifier_position.position.line,
+                line,
                 character: info.identifier_position.position.character,
             },
         )
@@ -88,6 +105,7 @@ pub async fn find_references(
                 "Failed to get workspace files".to_string(),
             )),
         },
+        Err(e @ LspManagerError::NotReady(_)) => Err(e),
         Err(e) => Err(LspManagerError::InternalError(format!(
             "Failed to get references: {}",
             e
@@ -112,11 +130,20 @@ pub async fn find_references(
         Ok(None)
     };
     match (filtered_reference_result, code_contexts_result) {
-        (Ok(references), Ok(code_contexts)) => HttpResponse::Ok().json(ReferencesResponse::from((
-            references,
-            code_contexts,
-            info.include_raw_response,
-        ))),
+        (Ok(references), Ok(code_contexts)) => {
+            let mut response =
+                ReferencesResponse::from((references, code_contexts, info.include_raw_response));
+            response
+                .references
+                .iter_mut()
+                .for_each(|reference| notebooks.map_position(reference));
+            if let Some(contexts) = response.context.as_mut() {
+                contexts
+                    .iter_mut()
+                    .for_each(|context| notebooks.map_range(&mut context.range));
+            }
+            HttpResponse::Ok().json(response)
+        }
         (Err(e), _) => {
             error!("Failed to get references: {}", e);
             match e {
@@ -137,6 +164,7 @@ pub async fn find_references(
                         error: format!("Unsupported file type: {}", path),
                     })
                 }
+                LspManagerError::NotReady(_) => not_ready_response(&e, manager.retry_after()),
             }
         }
         (_, Err(e)) => {
@@ -182,6 +210,7 @@ async fn fetch_code_context(
                             line: range.end.line,
                             character: 0,
                         },
+                        cell: None,
                     },
                 });
             }
@@ -195,12 +224,10 @@ async fn fetch_code_context(
 mod test {
     use super::*;
 
-    use actix_web::http::StatusCode;
-    use tokio::time::{sleep, Duration};
-
     use crate::api_types::{FilePosition, Position};
     use crate::initialize_app_state;
     use crate::test_utils::{python_sample_path, rust_sample_path, TestContext};
+    use actix_web::http::StatusCode;
 
     #[tokio::test]
     async fn test_python_references() -> Result<(), Box<dyn std::error::Error>> {
@@ -214,6 +241,7 @@ mod test {
                     line: 1,
                     character: 6,
                 },
+                cell: None,
             },
             include_code_context_lines: None,
             include_raw_response: false,
@@ -241,6 +269,7 @@ mod test {
                         line: 1,
                         character: 6,
                     },
+                    cell: None,
                 },
                 FilePosition {
                     path: String::from("main.py"),
@@ -248,6 +277,7 @@ mod test {
                         line: 1,
                         character: 18,
                     },
+                    cell: None,
                 },
                 FilePosition {
                     path: String::from("main.py"),
@@ -255,6 +285,7 @@ mod test {
                         line: 5,
                         character: 8,
                     },
+                    cell: None,
                 },
             ],
             context: None,
@@ -276,13 +307,12 @@ mod test {
                     line: 3,
                     character: 11,
                 },
+                cell: None,
             },
             include_code_context_lines: None,
             include_raw_response: false,
         });
 
-        sleep(Duration::from_secs(5)).await;
-
         let response = find_references(state, mock_request).await;
 
         assert_eq!(response.status(), StatusCode::OK);
@@ -305,6 +335,7 @@ mod test {
                         line: 1,
                         character: 17,
                     },
+                    cell: None,
                 },
                 FilePosition {
                     path: String::from("src/astar.rs"),
@@ -312,6 +343,7 @@ mod test {
                         line: 6,
                         character: 14,
                     },
+                    cell: None,
                 },
                 FilePosition {
                     path: String::from("src/astar.rs"),
@@ -319,6 +351,7 @@ mod test {
                         line: 7,
                         character: 16,
                     },
+                    cell: None,
                 },
                 FilePosition {
                     path: String::from("src/astar.rs"),
@@ -326,6 +359,7 @@ mod test {
                         line: 59,
                         character: 32,
                     },
+                    cell: None,
                 },
                 FilePosition {
                     path: String::from("src/astar.rs"),
@@ -333,6 +367,7 @@ mod test {
                         line: 76,
                         character: 35,
                     },
+                    cell: None,
                 },
                 FilePosition {
                     path: String::from("src/astar.rs"),
@@ -340,6 +375,7 @@ mod test {
                         line: 93,
                         character: 23,
                     },
+                    cell: None,
                 },
                 FilePosition {
                     path: String::from("src/node.rs"),
@@ -347,6 +383,7 @@ mod test {
                         line: 3,
                         character: 11,
                     },
+                    cell: None,
                 },
                 FilePosition {
                     path: String::from("src/node.rs"),
@@ -354,6 +391,7 @@ mod test {
                         line: 10,
                         character: 20,
                     },
+                    cell: None,
                 },
                 FilePosition {
                     path: String::from("src/node.rs"),
@@ -361,6 +399,7 @@ mod test {
                         line: 11,
                         character: 34,
                     },
+                    cell: None,
                 },
             ],
             context: None,
@@ -0,0 +1,76 @@
+use actix_web::web::Data;
+use actix_web::HttpResponse;
+
+use crate::api_types::HealthResponse;
+use crate::lsp::manager::server_health;
+use crate::AppState;
+
+/// Get the health of the language servers
+///
+/// Reports for each running language server its process, uptime, indexing state,
+/// in-flight requests, last error and negotiated capabilities.
+///
+/// Answers 503 while a server is still indexing, so it can be used as a readiness probe.
+#[utoipa::path(
+    get,
+    path = "/system/health",
+    tag = "system",
+    responses(
+        (status = 200, description = "All language servers are ready", body = HealthResponse),
+        (status = 503, description = "A language server is still indexing", body = HealthResponse)
+    )
+)]
+pub async fn health(data: Data<AppState>) -> HttpResponse {
+    let language_servers = server_health(&data.server_handles).await;
+    let ready = language_servers.iter().all(|server| server.ready);
+    let response = HealthResponse {
+        status: if ready { "ok" } else { "indexing" }.to_string(),
+        language_servers,
+    };
+    if ready {
+        HttpResponse::Ok().json(response)
+    } else {
+        HttpResponse::ServiceUnavailable().json(response)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_health() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // jedi doesn't report its indexing, it counts as busy for the startup timeout
+        let mut response = health(state.clone()).await;
+        for _ in 0..30 {
+            if response.status() == StatusCode::OK {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            response = health(state.clone()).await;
+        }
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let health: HealthResponse = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(health.status, "ok");
+        assert_eq!(health.language_servers.len(), 1);
+        let python = &health.language_servers[0];
+        assert_eq!(python.language, "python");
+        assert_eq!(python.name.as_deref(), Some("jedi-language-server"));
+        assert!(python.pid.is_some());
+        assert_eq!(python.in_flight_requests, 0);
+        assert!(python.capabilities.get("definitionProvider").is_some());
+        Ok(())
+    }
+}
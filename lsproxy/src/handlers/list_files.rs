@@ -3,6 +3,7 @@ use actix_web::HttpResponse;
 use log::error;
 
 use crate::api_types::ErrorResponse;
+use crate::handlers::not_ready_response;
 use crate::lsp::manager::LspManagerError;
 use crate::AppState;
 
@@ -18,7 +19,8 @@ use crate::AppState;
     responses(
         (status = 200, description = "Workspace files retrieved successfully", body = Vec<String>),
         (status = 400, description = "Bad request"),
-        (status = 500, description = "Internal server error")
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Language server is still indexing, retry after the `Retry-After` seconds")
     )
 )]
 pub async fn list_files(data: Data<AppState>) -> HttpResponse {
@@ -48,6 +50,7 @@ pub async fn list_files(data: Data<AppState>) -> HttpResponse {
                         error: format!("Unsupported file type: {}", path),
                     })
                 }
+                LspManagerError::NotReady(_) => not_ready_response(&e, manager.retry_after()),
             }
         }
     }
@@ -1,9 +1,30 @@
+use std::time::Duration;
+
+use actix_web::HttpResponse;
+
+use crate::api_types::ErrorResponse;
+use crate::lsp::manager::LspManagerError;
+
+mod compile_command;
 mod definitions_in_file;
 mod find_definition;
 mod find_references;
+mod health;
 mod list_files;
 mod read_source_code;
+mod rewrite;
+mod structural_search;
+mod workspace_symbols;
 pub use self::{
-    definitions_in_file::*, find_definition::*, find_references::*, list_files::*,
-    read_source_code::*,
+    compile_command::*, definitions_in_file::*, find_definition::*, find_references::*, health::*,
+    list_files::*, read_source_code::*, rewrite::*, structural_search::*, workspace_symbols::*,
 };
+
+/// Response for a request that gave up waiting for a language server to finish indexing.
+pub(crate) fn not_ready_response(error: &LspManagerError, retry_after: Duration) -> HttpResponse {
+    HttpResponse::ServiceUnavailable()
+        .insert_header(("Retry-After", retry_after.as_secs().to_string()))
+        .json(ErrorResponse {
+            error: error.to_string(),
+        })
+}
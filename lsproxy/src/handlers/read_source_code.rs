@@ -6,6 +6,7 @@ use lsp_types::{Position as LspPosition, Range};
 use serde::Serialize;
 use utoipa::ToSchema;
 
+use crate::utils::notebook::NotebookPositions;
 use crate::AppState;
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -41,13 +42,22 @@ pub async fn read_source_code(data: Data<AppState>, info: Json<FileRange>) -> Ht
         })
         .unwrap();
 
+    // Ranges in a notebook cell are relative to the cell
+    let line_offset = match NotebookPositions::default().document_line(&info.path, info.cell, 0) {
+        Ok(line) => line,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Invalid range: {}", e),
+            })
+        }
+    };
     let lsp_range = Some(Range::new(
         LspPosition {
-            line: info.start.line,
+            line: info.start.line + line_offset,
             character: info.start.character,
         },
         LspPosition {
-            line: info.end.line,
+            line: info.end.line + line_offset,
             character: info.end.character,
         },
     ));
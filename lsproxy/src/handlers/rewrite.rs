@@ -0,0 +1,137 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{ErrorResponse, FileRewrite, RewriteRequest, RewriteResponse};
+use crate::ast_grep::client::search_rule;
+use crate::handlers::not_ready_response;
+use crate::lsp::manager::LspManagerError;
+use crate::AppState;
+
+/// Rewrite the code matching an ast-grep pattern or rule (uses ast-grep)
+///
+/// Returns a unified diff of the changes to each file with a match. The files are only changed
+/// when `apply` is set. Files that aren't valid UTF-8 are never changed, they are listed in
+/// `skipped`.
+///
+/// e.g. the pattern `requests.get($URL)` with the fix `requests.get($URL, timeout=10)` in
+/// `python` changes `src/client.py`:
+/// ```
+/// 2: def fetch(url):
+/// 3:     return requests.get(url)
+/// ```
+/// to
+/// ```
+/// 2: def fetch(url):
+/// 3:     return requests.get(url, timeout=10)
+/// ```
+#[utoipa::path(
+    post,
+    path = "/search/rewrite",
+    tag = "search",
+    request_body = RewriteRequest,
+    responses(
+        (status = 200, description = "Rewrite computed successfully", body = RewriteResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Language server is still indexing, retry after the `Retry-After` seconds")
+    )
+)]
+pub async fn rewrite(data: Data<AppState>, info: Json<RewriteRequest>) -> HttpResponse {
+    info!(
+        "Received rewrite request for language: {}, apply: {}",
+        info.language, info.apply
+    );
+    let rule = match search_rule(
+        info.pattern.as_deref(),
+        info.rule.as_deref(),
+        &info.language,
+        Some(&info.fix),
+    ) {
+        Ok(rule) => rule,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Invalid rule: {}", e),
+            })
+        }
+    };
+    let path_glob = match info
+        .path_glob
+        .as_deref()
+        .map(glob::Pattern::new)
+        .transpose()
+    {
+        Ok(path_glob) => path_glob,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Invalid path glob: {}", e),
+            })
+        }
+    };
+
+    let manager = match data.manager.lock() {
+        Ok(manager) => manager.clone(),
+        Err(e) => {
+            error!("Failed to acquire lock on LSP manager: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Internal server error".to_string(),
+            });
+        }
+    };
+    match manager.rewrite(&rule, path_glob.as_ref(), info.apply).await {
+        Ok((edits, skipped)) => HttpResponse::Ok().json(RewriteResponse {
+            files: edits.into_iter().map(FileRewrite::from).collect(),
+            applied: info.apply,
+            skipped,
+        }),
+        Err(e) => {
+            error!("Rewrite failed: {}", e);
+            match e {
+                LspManagerError::NotReady(_) => not_ready_response(&e, manager.retry_after()),
+                _ => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: format!("Rewrite failed: {}", e),
+                }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_rewrite_preview() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let mock_request = Json(RewriteRequest {
+            pattern: Some(String::from("plt.plot($X, $Y)")),
+            rule: None,
+            fix: String::from("plt.scatter($X, $Y)"),
+            language: String::from("python"),
+            path_glob: None,
+            apply: false,
+        });
+        let response = rewrite(state, mock_request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let rewrite_response: RewriteResponse = serde_json::from_slice(&bytes).unwrap();
+        assert!(!rewrite_response.applied);
+        assert!(rewrite_response.skipped.is_empty());
+        assert_eq!(rewrite_response.files.len(), 1);
+        assert_eq!(rewrite_response.files[0].path, "main.py");
+        assert_eq!(rewrite_response.files[0].matches, 2);
+        assert!(rewrite_response.files[0]
+            .diff
+            .contains("+plt.scatter([v[0] for v in result], [v[1] for v in result])"));
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use actix_web::web::{Data, Json};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{
+    ErrorResponse, StructuralMatch, StructuralSearchRequest, StructuralSearchResponse,
+};
+use crate::ast_grep::client::search_rule;
+use crate::handlers::not_ready_response;
+use crate::lsp::manager::LspManagerError;
+use crate::AppState;
+
+/// Search the workspace for code matching an ast-grep pattern or rule (uses ast-grep)
+///
+/// Returns every match in the files of the given language, with the ranges captured by the
+/// meta-variables of the pattern.
+///
+/// e.g. the pattern `requests.get($URL)` in `python` matches line 3 of `src/client.py`:
+/// ```
+/// 2: def fetch(url):
+/// 3:     return requests.get(url)
+/// ___________^
+/// ```
+/// with `URL` capturing `url`.
+#[utoipa::path(
+    post,
+    path = "/search/structural",
+    tag = "search",
+    request_body = StructuralSearchRequest,
+    responses(
+        (status = 200, description = "Matches retrieved successfully", body = StructuralSearchResponse),
+        (status = 400, description = "Bad request"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Language server is still indexing, retry after the `Retry-After` seconds")
+    )
+)]
+pub async fn structural_search(
+    data: Data<AppState>,
+    info: Json<StructuralSearchRequest>,
+) -> HttpResponse {
+    info!(
+        "Received structural search request for language: {}",
+        info.language
+    );
+    let rule = match search_rule(
+        info.pattern.as_deref(),
+        info.rule.as_deref(),
+        &info.language,
+        None,
+    ) {
+        Ok(rule) => rule,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Invalid rule: {}", e),
+            })
+        }
+    };
+    let path_glob = match info
+        .path_glob
+        .as_deref()
+        .map(glob::Pattern::new)
+        .transpose()
+    {
+        Ok(path_glob) => path_glob,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!("Invalid path glob: {}", e),
+            })
+        }
+    };
+
+    let manager = match data.manager.lock() {
+        Ok(manager) => manager.clone(),
+        Err(e) => {
+            error!("Failed to acquire lock on LSP manager: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Internal server error".to_string(),
+            });
+        }
+    };
+    match manager.structural_search(&rule, path_glob.as_ref()).await {
+        Ok(matches) => HttpResponse::Ok().json(StructuralSearchResponse {
+            matches: matches.into_iter().map(StructuralMatch::from).collect(),
+        }),
+        Err(e) => {
+            error!("Structural search failed: {}", e);
+            match e {
+                LspManagerError::NotReady(_) => not_ready_response(&e, manager.retry_after()),
+                _ => HttpResponse::InternalServerError().json(ErrorResponse {
+                    error: format!("Search failed: {}", e),
+                }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_structural_search_pattern() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let mock_request = Json(StructuralSearchRequest {
+            pattern: Some(String::from("plt.plot($$$ARGS)")),
+            rule: None,
+            language: String::from("python"),
+            path_glob: Some(String::from("*.py")),
+        });
+        let response = structural_search(state, mock_request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body();
+        let bytes = actix_web::body::to_bytes(body).await.unwrap();
+        let search_response: StructuralSearchResponse = serde_json::from_slice(&bytes).unwrap();
+        assert!(!search_response.matches.is_empty());
+        for structural_match in &search_response.matches {
+            assert!(structural_match.text.starts_with("plt.plot("));
+            assert!(structural_match.meta_variables.contains_key("ARGS"));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_structural_search_invalid_rule() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        let mock_request = Json(StructuralSearchRequest {
+            pattern: None,
+            rule: Some(String::from("kind: not_a_python_kind")),
+            language: String::from("python"),
+            path_glob: None,
+        });
+        let response = structural_search(state, mock_request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        Ok(())
+    }
+}
@@ -0,0 +1,149 @@
+use actix_web::web::{Data, Query};
+use actix_web::HttpResponse;
+use log::{error, info};
+
+use crate::api_types::{ErrorResponse, Symbol, WorkspaceSymbolsRequest};
+use crate::handlers::not_ready_response;
+use crate::lsp::manager::LspManagerError;
+use crate::utils::notebook::NotebookPositions;
+use crate::AppState;
+
+/// Search the symbols of the whole workspace (uses ast-grep)
+///
+/// Returns the symbols whose name contains `query`, ignoring case, and whose kind is `kind`,
+/// sorted by file and position. Both filters are optional.
+///
+/// The symbols come from an index of the workspace built with the same rules as
+/// `definitions-in-file`, kept up to date as files change. Right after startup the index is
+/// catching up with the changes made while the server was down, requests fail with 503 until
+/// it is done.
+///
+/// e.g. the query `user` with the kind `class` finds `User` on line 0 of `src/main.py`:
+/// ```
+/// 0: class User:
+/// _________^
+/// 1:     def __init__(self, name, age):
+/// ```
+#[utoipa::path(
+    get,
+    path = "/symbol/workspace-symbols",
+    tag = "symbol",
+    params(WorkspaceSymbolsRequest),
+    responses(
+        (status = 200, description = "Symbols retrieved successfully", body = Vec<Symbol>),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Language server is still indexing, retry after the `Retry-After` seconds")
+    )
+)]
+pub async fn workspace_symbols(
+    data: Data<AppState>,
+    info: Query<WorkspaceSymbolsRequest>,
+) -> HttpResponse {
+    info!(
+        "Received workspace symbols request for query: {:?}, kind: {:?}",
+        info.query, info.kind
+    );
+    let manager = match data.manager.lock() {
+        Ok(manager) => manager.clone(),
+        Err(e) => {
+            error!("Failed to acquire lock on LSP manager: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: "Internal server error".to_string(),
+            });
+        }
+    };
+    let mut notebooks = NotebookPositions::default();
+    let symbols = match manager
+        .workspace_symbols(
+            info.query.as_deref(),
+            info.kind.as_deref(),
+            info.include_documentation,
+        )
+        .await
+    {
+        Ok(symbols) => symbols,
+        Err(e @ LspManagerError::NotReady(_)) => {
+            return not_ready_response(&e, manager.retry_after())
+        }
+        Err(e) => {
+            error!("Failed to search the workspace symbols: {}", e);
+            return HttpResponse::InternalServerError().json(ErrorResponse {
+                error: format!("Failed to search the workspace symbols: {}", e),
+            });
+        }
+    };
+    let symbols: Vec<Symbol> = symbols
+        .into_iter()
+        .map(|mut symbol| {
+            notebooks.map_position(&mut symbol.identifier_position);
+            notebooks.map_range(&mut symbol.range);
+            symbol
+        })
+        .collect();
+    HttpResponse::Ok().json(symbols)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    use actix_web::http::StatusCode;
+
+    use crate::initialize_app_state;
+    use crate::test_utils::{python_sample_path, TestContext};
+
+    #[tokio::test]
+    async fn test_python_workspace_symbols() -> Result<(), Box<dyn std::error::Error>> {
+        let _context = TestContext::setup(&python_sample_path(), false).await?;
+        let state = initialize_app_state().await?;
+
+        // The index is built in the background after startup
+        let mut symbols = Vec::new();
+        for _ in 0..50 {
+            let mock_request = Query(WorkspaceSymbolsRequest {
+                query: Some(String::from("SEARCH")),
+                kind: Some(String::from("function")),
+                include_documentation: true,
+            });
+            let response = workspace_symbols(state.clone(), mock_request).await;
+            if response.status() == StatusCode::SERVICE_UNAVAILABLE {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+            assert_eq!(response.status(), StatusCode::OK);
+            let bytes = actix_web::body::to_bytes(response.into_body())
+                .await
+                .unwrap();
+            symbols = serde_json::from_slice::<Vec<Symbol>>(&bytes).unwrap();
+            if !symbols.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let found: Vec<(String, String, u32)> = symbols
+            .into_iter()
+            .map(|symbol| {
+                (
+                    symbol.name,
+                    symbol.identifier_position.path,
+                    symbol.identifier_position.position.line,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    String::from("initialize_search"),
+                    String::from("search.py"),
+                    0
+                ),
+                (String::from("a_star_search"), String::from("search.py"), 11),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@ use actix_web::{
 use api_types::{CodeContext, ErrorResponse, FileRange, Position};
 use handlers::read_source_code;
 use log::warn;
+use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
 use std::io::Write;
@@ -16,17 +17,23 @@ use utoipa_swagger_ui::SwaggerUi;
 
 pub mod api_types;
 mod ast_grep;
+mod config;
 mod handlers;
 mod lsp;
 mod utils;
 
 use crate::api_types::{
-    get_mount_dir, set_global_mount_dir, DefinitionResponse, FilePosition, FileSymbolsRequest,
-    GetDefinitionRequest, GetReferencesRequest, ReferencesResponse, SupportedLanguages, Symbol,
-    SymbolResponse,
+    get_mount_dir, set_global_mount_dir, CompileCommandRequest, CompileCommandResponse,
+    DefinitionResponse, FilePosition, FileRewrite, FileSymbolsRequest, GetDefinitionRequest,
+    GetReferencesRequest, HealthResponse, LanguageServerHealth, ReferencesResponse, RewriteRequest,
+    RewriteResponse, StructuralMatch, StructuralSearchRequest, StructuralSearchResponse,
+    SupportedLanguages, Symbol, SymbolResponse, WorkspaceSymbolsRequest,
 };
-use crate::handlers::{definitions_in_file, find_definition, find_references, list_files};
-use crate::lsp::manager::Manager;
+use crate::handlers::{
+    compile_command, definitions_in_file, find_definition, find_references, health, list_files,
+    rewrite, structural_search, workspace_symbols,
+};
+use crate::lsp::manager::{Manager, ServerHandles};
 // use crate::utils::doc_utils::make_code_sample;
 
 pub fn check_mount_dir() -> std::io::Result<()> {
@@ -45,11 +52,16 @@ pub fn check_mount_dir() -> std::io::Result<()> {
         )
     ),
     paths(
+        crate::handlers::compile_command,
         crate::handlers::definitions_in_file,
         crate::handlers::find_definition,
         crate::handlers::find_references,
+        crate::handlers::health,
         crate::handlers::list_files,
         crate::handlers::read_source_code,
+        crate::handlers::rewrite,
+        crate::handlers::structural_search,
+        crate::handlers::workspace_symbols,
     ),
     components(
         schemas(
@@ -66,6 +78,17 @@ pub fn check_mount_dir() -> std::io::Result<()> {
             ErrorResponse,
             CodeContext,
             FileRange,
+            CompileCommandRequest,
+            CompileCommandResponse,
+            HealthResponse,
+            LanguageServerHealth,
+            StructuralSearchRequest,
+            StructuralSearchResponse,
+            StructuralMatch,
+            RewriteRequest,
+            RewriteResponse,
+            FileRewrite,
+            WorkspaceSymbolsRequest,
         )
     ),
     tags(
@@ -79,6 +102,8 @@ pub struct ApiDoc;
 
 pub struct AppState {
     manager: Arc<Mutex<Manager>>,
+    /// Read by the health probe, which must answer while a request holds the manager lock.
+    server_handles: Arc<HashMap<String, ServerHandles>>,
 }
 
 pub async fn initialize_app_state() -> Result<Data<AppState>, Box<dyn std::error::Error>> {
@@ -110,8 +135,12 @@ pub async fn initialize_app_state_with_mount_dir(
         .unwrap()
         .start_langservers(&mount_dir)
         .await?;
+    let server_handles = manager.lock().unwrap().server_handles();
 
-    Ok(Data::new(AppState { manager }))
+    Ok(Data::new(AppState {
+        manager,
+        server_handles,
+    }))
 }
 
 // Helper enum for cleaner matching
@@ -170,10 +199,20 @@ pub async fn run_server_with_port_and_host(
                     api_scope.service(resource(path).route(post().to(find_references))),
                 ("/symbol/definitions-in-file", Some(Method::Get)) =>
                     api_scope.service(resource(path).route(get().to(definitions_in_file))),
+                ("/symbol/workspace-symbols", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(workspace_symbols))),
+                ("/system/health", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(health))),
                 ("/workspace/list-files", Some(Method::Get)) =>
                     api_scope.service(resource(path).route(get().to(list_files))),
+                ("/workspace/compile-command", Some(Method::Get)) =>
+                    api_scope.service(resource(path).route(get().to(compile_command))),
                 ("/workspace/read-source-code", Some(Method::Post)) =>
                     api_scope.service(resource(path).route(post().to(read_source_code))),
+                ("/search/rewrite", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(rewrite))),
+                ("/search/structural", Some(Method::Post)) =>
+                    api_scope.service(resource(path).route(post().to(structural_search))),
                 (p, m) => panic!(
                     "Invalid path configuration for {}: {:?}. Ensure the OpenAPI spec matches your handlers.", 
                     p,
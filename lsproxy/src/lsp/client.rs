@@ -1,16 +1,21 @@
 use crate::lsp::json_rpc::JsonRpc;
 use crate::lsp::process::Process;
-use crate::lsp::{ExpectedMessageKey, InnerMessage, JsonRpcHandler, ProcessHandler};
+use crate::lsp::{
+    ExpectedMessageKey, InnerMessage, JsonRpcHandler, JsonRpcMessage, ProcessHandler,
+};
 use crate::utils::file_utils::{detect_language_string, search_directories};
+use crate::utils::notebook::is_notebook;
 use async_trait::async_trait;
 use log::{debug, error, warn};
 use lsp_types::{
-    ClientCapabilities, DidOpenTextDocumentParams, DocumentSymbolClientCapabilities,
-    DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams, GotoDefinitionResponse,
-    InitializeParams, InitializeResult, Location, PartialResultParams, Position,
-    PublishDiagnosticsClientCapabilities, ReferenceContext, ReferenceParams, TagSupport,
+    ClientCapabilities, DidChangeWatchedFilesParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentSymbolClientCapabilities, DocumentSymbolParams,
+    DocumentSymbolResponse, FileChangeType, FileEvent, GotoDefinitionParams,
+    GotoDefinitionResponse, InitializeParams, InitializeResult, Location, PartialResultParams,
+    Position, PublishDiagnosticsClientCapabilities, ReferenceContext, ReferenceParams, TagSupport,
     TextDocumentClientCapabilities, TextDocumentIdentifier, TextDocumentItem,
-    TextDocumentPositionParams, Url, WorkDoneProgressParams, WorkspaceFolder,
+    TextDocumentPositionParams, Url, WindowClientCapabilities, WorkDoneProgressParams,
+    WorkspaceFolder,
 };
 use std::error::Error;
 use std::path::{Path, PathBuf};
@@ -19,7 +24,7 @@ use crate::utils::workspace_documents::{
     DidOpenConfiguration, WorkspaceDocuments, WorkspaceDocumentsHandler, DEFAULT_EXCLUDE_PATTERNS,
 };
 
-use super::PendingRequests;
+use super::{PendingRequests, ServerReadiness};
 
 #[async_trait]
 pub trait LspClient: Send {
@@ -59,6 +64,12 @@ pub trait LspClient: Send {
             ..Default::default()
         });
 
+        // Lets servers report indexing with `$/progress`
+        capabilities.window = Some(WindowClientCapabilities {
+            work_done_progress: Some(true),
+            ..Default::default()
+        });
+
         capabilities.experimental = Some(serde_json::json!({
             "serverStatusNotification": true
         }));
@@ -74,10 +85,16 @@ pub trait LspClient: Send {
                     .unwrap(),
             ),
             root_uri: Some(Url::from_file_path(&root_path).unwrap()), // primarily for python
+            initialization_options: self.get_initialization_options(),
             ..Default::default()
         }
     }
 
+    /// Server specific `initializationOptions` sent with the `initialize` request.
+    fn get_initialization_options(&mut self) -> Option<serde_json::Value> {
+        None
+    }
+
     async fn send_request(
         &mut self,
         method: &str,
@@ -102,6 +119,8 @@ pub trait LspClient: Send {
             if error.message.starts_with("KeyError") {
                 return Ok(serde_json::Value::Array(vec![]));
             }
+            self.get_pending_requests()
+                .set_last_error(format!("{}: {}", method, error));
             Err(error.into())
         } else {
             Ok(serde_json::Value::Null)
@@ -109,15 +128,27 @@ pub trait LspClient: Send {
     }
 
     async fn start_response_listener(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let process = self.get_process().clone();
+        let mut process = self.get_process().clone();
         let pending_requests = self.get_pending_requests().clone();
         let json_rpc = self.get_json_rpc().clone();
+        let readiness = self.get_readiness().clone();
 
         tokio::spawn(async move {
             loop {
                 if let Ok(raw_response) = process.receive().await {
                     if let Ok(message) = json_rpc.parse_message(&raw_response) {
-                        if let Some(id) = message.id {
+                        readiness.handle_message(&message);
+                        if let (Some(id), Some(method)) = (message.id, message.method.as_deref()) {
+                            // A request from the server, it waits for our answer
+                            debug!("Received server request {} {}", id, method);
+                            let response =
+                                json_rpc.create_response(id, server_request_result(&message));
+                            let response =
+                                format!("Content-Length: {}\r\n\r\n{}", response.len(), response);
+                            if let Err(e) = process.send(&response).await {
+                                error!("Failed to answer server request {}: {}", id, e);
+                            }
+                        } else if let Some(id) = message.id {
                             debug!("Received response for request {}", id);
                             if let Ok(Some(sender)) = pending_requests.remove_request(id).await {
                                 if sender.send(message.clone()).is_err() {
@@ -183,32 +214,30 @@ pub trait LspClient: Send {
         self.get_process().send(&message).await
     }
 
-    async fn text_document_definition(
+    /// Sends `textDocument/didOpen` for `file_path` unless the server reads it from disk or
+    /// already has it open.
+    async fn open_if_needed(
         &mut self,
         file_path: &str,
-        position: Position,
-    ) -> Result<GotoDefinitionResponse, Box<dyn Error + Send + Sync>> {
-        debug!(
-            "Requesting goto definition for {}, line {}, character {}",
-            file_path, position.line, position.character
-        );
-
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
         let needs_open = {
             let workspace_documents = self.get_workspace_documents();
-            workspace_documents.get_did_open_configuration() == DidOpenConfiguration::Lazy
+            // Notebooks are always opened, the server can't read them from disk
+            (workspace_documents.get_did_open_configuration() == DidOpenConfiguration::Lazy
+                || is_notebook(file_path))
                 && !workspace_documents.is_did_open_document(file_path)
         };
 
-        // If needed, read the document text and send didOpen
         if needs_open {
             let document_text = self
                 .get_workspace_documents()
                 .read_text_document(&PathBuf::from(file_path), None)
                 .await?;
+            let language_id = self.get_language_id(file_path)?;
 
             self.text_document_did_open(TextDocumentItem {
                 uri: Url::from_file_path(file_path).unwrap(),
-                language_id: detect_language_string(file_path)?,
+                language_id,
                 version: 1,
                 text: document_text,
             })
@@ -217,6 +246,82 @@ pub trait LspClient: Send {
             self.get_workspace_documents()
                 .add_did_open_document(file_path);
         }
+        Ok(())
+    }
+
+    async fn text_document_did_close(
+        &mut self,
+        uri: Url,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let params = DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier { uri },
+        };
+        let notification = self
+            .get_json_rpc()
+            .create_notification("textDocument/didClose", serde_json::to_value(params)?);
+        let message = format!(
+            "Content-Length: {}\r\n\r\n{}",
+            notification.len(),
+            notification
+        );
+        self.get_process().send(&message).await
+    }
+
+    /// Tells the server `file_path` was changed on disk to `text`.
+    ///
+    /// Servers read opened documents from their notifications rather than from disk, so an opened
+    /// document is closed and opened again with the new text.
+    async fn reload_text_document(
+        &mut self,
+        file_path: &str,
+        text: String,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        debug!("Reloading {}", file_path);
+        let uri = Url::from_file_path(file_path).map_err(|_| "Invalid file path")?;
+        if self
+            .get_workspace_documents()
+            .is_did_open_document(file_path)
+        {
+            self.text_document_did_close(uri.clone()).await?;
+            let language_id = self.get_language_id(file_path)?;
+            return self
+                .text_document_did_open(TextDocumentItem {
+                    uri,
+                    language_id,
+                    version: 1,
+                    text,
+                })
+                .await;
+        }
+        let params = DidChangeWatchedFilesParams {
+            changes: vec![FileEvent {
+                uri,
+                typ: FileChangeType::CHANGED,
+            }],
+        };
+        let notification = self.get_json_rpc().create_notification(
+            "workspace/didChangeWatchedFiles",
+            serde_json::to_value(params)?,
+        );
+        let message = format!(
+            "Content-Length: {}\r\n\r\n{}",
+            notification.len(),
+            notification
+        );
+        self.get_process().send(&message).await
+    }
+
+    async fn text_document_definition(
+        &mut self,
+        file_path: &str,
+        position: Position,
+    ) -> Result<GotoDefinitionResponse, Box<dyn Error + Send + Sync>> {
+        debug!(
+            "Requesting goto definition for {}, line {}, character {}",
+            file_path, position.line, position.character
+        );
+
+        self.open_if_needed(file_path).await?;
 
         let params = GotoDefinitionParams {
             text_document_position_params: TextDocumentPositionParams {
@@ -244,7 +349,17 @@ pub trait LspClient: Send {
         };
 
         debug!("Received goto definition response");
-        Ok(goto_resp)
+        self.resolve_definition(goto_resp).await
+    }
+
+    /// Post-processes the response of `textDocument/definition`.
+    ///
+    /// Servers can override this to point definitions at sources instead of build outputs.
+    async fn resolve_definition(
+        &mut self,
+        response: GotoDefinitionResponse,
+    ) -> Result<GotoDefinitionResponse, Box<dyn Error + Send + Sync>> {
+        Ok(response)
     }
 
     async fn text_document_symbols(
@@ -252,6 +367,7 @@ pub trait LspClient: Send {
         file_path: &str,
     ) -> Result<DocumentSymbolResponse, Box<dyn Error + Send + Sync>> {
         debug!("Requesting document symbols for {}", file_path);
+        self.open_if_needed(file_path).await?;
         let params = DocumentSymbolParams {
             text_document: TextDocumentIdentifier {
                 uri: Url::from_file_path(file_path).unwrap(),
@@ -267,7 +383,11 @@ pub trait LspClient: Send {
             )
             .await?;
 
-        let symbols: DocumentSymbolResponse = serde_json::from_value(result)?;
+        let symbols: DocumentSymbolResponse = if result.is_null() {
+            DocumentSymbolResponse::Nested(Vec::new())
+        } else {
+            serde_json::from_value(result)?
+        };
         debug!("Received document symbols response");
         Ok(symbols)
     }
@@ -277,31 +397,7 @@ pub trait LspClient: Send {
         file_path: &str,
         position: Position,
     ) -> Result<Vec<Location>, Box<dyn Error + Send + Sync>> {
-        // Get the configuration and check if document is opened first
-        let needs_open = {
-            let workspace_documents = self.get_workspace_documents();
-            workspace_documents.get_did_open_configuration() == DidOpenConfiguration::Lazy
-                && !workspace_documents.is_did_open_document(file_path)
-        };
-
-        // If needed, read the document text and send didOpen
-        if needs_open {
-            let document_text = self
-                .get_workspace_documents()
-                .read_text_document(&PathBuf::from(file_path), None)
-                .await?;
-
-            self.text_document_did_open(TextDocumentItem {
-                uri: Url::from_file_path(file_path).unwrap(),
-                language_id: detect_language_string(file_path)?,
-                version: 1,
-                text: document_text,
-            })
-            .await?;
-
-            self.get_workspace_documents()
-                .add_did_open_document(file_path);
-        }
+        self.open_if_needed(file_path).await?;
 
         let params = ReferenceParams {
             text_document_position: TextDocumentPositionParams {
@@ -337,8 +433,15 @@ pub trait LspClient: Send {
         vec![".git".to_string()]
     }
 
+    /// Returns the `languageId` sent with `textDocument/didOpen` for the file.
+    fn get_language_id(&mut self, file_path: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        Ok(detect_language_string(file_path)?)
+    }
+
     fn get_pending_requests(&mut self) -> &mut PendingRequests;
 
+    fn get_readiness(&mut self) -> &mut ServerReadiness;
+
     fn get_workspace_documents(&mut self) -> &mut WorkspaceDocumentsHandler;
     /// Sets up the workspace for the language server.
     ///
@@ -410,3 +513,20 @@ pub trait LspClient: Send {
         Ok(workspace_folders.into_iter().collect())
     }
 }
+
+/// Answers requests sent by the server, we don't support any of them beyond acknowledging.
+fn server_request_result(message: &JsonRpcMessage) -> serde_json::Value {
+    match message.method.as_deref() {
+        // One setting per requested item, null lets the server use its defaults
+        Some("workspace/configuration") => serde_json::Value::Array(
+            message
+                .params
+                .as_ref()
+                .and_then(|params| params.get("items"))
+                .and_then(|items| items.as_array())
+                .map(|items| vec![serde_json::Value::Null; items.len()])
+                .unwrap_or_default(),
+        ),
+        _ => serde_json::Value::Null,
+    }
+}
@@ -11,6 +11,7 @@ use tokio::sync::Mutex;
 pub trait JsonRpc: Send + Sync {
     fn create_request(&self, method: &str, params: Option<Value>) -> (u64, String);
     fn create_notification(&self, method: &str, params: Value) -> String;
+    fn create_response(&self, id: u64, result: Value) -> String;
     fn parse_message(&self, data: &str) -> Result<JsonRpcMessage, JsonRpcError>;
 }
 
@@ -79,6 +80,15 @@ impl JsonRpc for JsonRpcHandler {
         .to_string()
     }
 
+    fn create_response(&self, id: u64, result: Value) -> String {
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "result": result
+        })
+        .to_string()
+    }
+
     fn parse_message(&self, data: &str) -> Result<JsonRpcMessage, JsonRpcError> {
         serde_json::from_str(data).map_err(|e| JsonRpcError {
             code: -32700,
@@ -98,6 +108,7 @@ pub struct ExpectedMessageKey {
 pub struct PendingRequests {
     request_channels: Arc<Mutex<HashMap<u64, Sender<JsonRpcMessage>>>>,
     notification_channels: Arc<Mutex<HashMap<ExpectedMessageKey, Sender<JsonRpcMessage>>>>,
+    last_error: Arc<std::sync::Mutex<Option<String>>>,
 }
 
 impl PendingRequests {
@@ -105,9 +116,24 @@ impl PendingRequests {
         Self {
             request_channels: Arc::new(Mutex::new(HashMap::new())),
             notification_channels: Arc::new(Mutex::new(HashMap::new())),
+            last_error: Arc::new(std::sync::Mutex::new(None)),
         }
     }
 
+    /// Number of requests waiting for a response.
+    pub async fn in_flight_requests(&self) -> usize {
+        self.request_channels.lock().await.len()
+    }
+
+    /// Records the latest error answered by the server.
+    pub fn set_last_error(&self, error: String) {
+        *self.last_error.lock().unwrap() = Some(error);
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+
     pub async fn add_request(
         &self,
         id: u64,
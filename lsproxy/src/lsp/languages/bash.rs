@@ -0,0 +1,88 @@
+use std::path::Path;
+use std::process::Stdio;
+
+use async_trait::async_trait;
+use notify_debouncer_mini::DebouncedEvent;
+use tokio::process::Command;
+use tokio::sync::broadcast::Receiver;
+
+use crate::lsp::{JsonRpcHandler, LspClient, PendingRequests, ProcessHandler, ServerReadiness};
+
+use crate::utils::workspace_documents::{
+    DidOpenConfiguration, WorkspaceDocumentsHandler, BASH_FILE_PATTERNS, BASH_ROOT_FILES,
+    DEFAULT_EXCLUDE_PATTERNS,
+};
+
+pub struct BashLanguageClient {
+    process: ProcessHandler,
+    json_rpc: JsonRpcHandler,
+    workspace_documents: WorkspaceDocumentsHandler,
+    pending_requests: PendingRequests,
+    readiness: ServerReadiness,
+}
+
+#[async_trait]
+impl LspClient for BashLanguageClient {
+    fn get_process(&mut self) -> &mut ProcessHandler {
+        &mut self.process
+    }
+
+    fn get_json_rpc(&mut self) -> &mut JsonRpcHandler {
+        &mut self.json_rpc
+    }
+
+    fn get_root_files(&mut self) -> Vec<String> {
+        BASH_ROOT_FILES.iter().map(|&s| s.to_string()).collect()
+    }
+
+    fn get_workspace_documents(&mut self) -> &mut WorkspaceDocumentsHandler {
+        &mut self.workspace_documents
+    }
+
+    fn get_pending_requests(&mut self) -> &mut PendingRequests {
+        &mut self.pending_requests
+    }
+
+    fn get_readiness(&mut self) -> &mut ServerReadiness {
+        &mut self.readiness
+    }
+}
+
+impl BashLanguageClient {
+    pub async fn new(
+        root_path: &str,
+        watch_events_rx: Receiver<DebouncedEvent>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let process = Command::new("bash-language-server")
+            .arg("start")
+            .current_dir(root_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)?;
+
+        let process_handler = ProcessHandler::new(process)
+            .await
+            .map_err(|e| format!("Failed to create ProcessHandler: {}", e))?;
+        let json_rpc_handler = JsonRpcHandler::new();
+        let workspace_documents = WorkspaceDocumentsHandler::new(
+            Path::new(root_path),
+            BASH_FILE_PATTERNS.iter().map(|&s| s.to_string()).collect(),
+            DEFAULT_EXCLUDE_PATTERNS
+                .iter()
+                .map(|&s| s.to_string())
+                .collect(),
+            watch_events_rx,
+            DidOpenConfiguration::Lazy,
+        );
+
+        Ok(Self {
+            process: process_handler,
+            json_rpc: json_rpc_handler,
+            workspace_documents,
+            pending_requests: PendingRequests::new(),
+            readiness: ServerReadiness::new(),
+        })
+    }
+}
@@ -6,10 +6,11 @@ use std::error::Error;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 
+use crate::config::state_dir;
 use crate::utils::file_utils::{search_directories, search_files};
 use crate::utils::workspace_documents::DidOpenConfiguration;
 use crate::{
-    lsp::{JsonRpcHandler, LspClient, PendingRequests, ProcessHandler},
+    lsp::{JsonRpcHandler, LspClient, PendingRequests, ProcessHandler, ServerReadiness},
     utils::workspace_documents::{
         WorkspaceDocumentsHandler, CPP_ROOT_FILES, C_AND_CPP_FILE_PATTERNS,
         DEFAULT_EXCLUDE_PATTERNS,
@@ -28,6 +29,7 @@ pub struct ClangdClient {
     json_rpc: JsonRpcHandler,
     workspace_documents: WorkspaceDocumentsHandler,
     pending_requests: PendingRequests,
+    readiness: ServerReadiness,
 }
 
 #[async_trait]
@@ -52,32 +54,8 @@ impl LspClient for ClangdClient {
         &mut self.pending_requests
     }
 
-    async fn setup_workspace(
-        &mut self,
-        root_path: &str,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let compile_db_files = search_files(
-            Path::new(root_path),
-            vec![String::from("**/compile_commands.json")],
-            vec![String::from("**/.git")],
-            false,
-        )?;
-
-        if compile_db_files.is_empty() {
-            debug!("Couldn't find compile comands json, falling back to generation");
-            // this is a workaround to avoid building the entire project
-            let commands = generate_compile_commands(root_path.to_string())?;
-
-            let json = serde_json::to_string_pretty(&commands)?;
-
-            write(Path::new(root_path).join("compile_commands.json"), json).await?;
-
-            debug!(
-                "Generated compile_commands.json with {} entries",
-                commands.len()
-            );
-        }
-        Ok(())
+    fn get_readiness(&mut self) -> &mut ServerReadiness {
+        &mut self.readiness
     }
 
     async fn get_initialize_params(&mut self, root_path: String) -> InitializeParams {
@@ -86,7 +64,7 @@ impl LspClient for ClangdClient {
             capabilities,
             root_uri: Some(Url::from_file_path(root_path).unwrap()),
             initialization_options: Some(serde_json::json!({
-                "clangdFileStatus": true, // TODO: actually wait for the status when hitting a file
+                "clangdFileStatus": true,
             })),
             ..Default::default()
         }
@@ -100,8 +78,15 @@ impl ClangdClient {
     ) -> Result<Self, Box<dyn Error + Send + Sync>> {
         let debug_file = std::fs::File::create("/tmp/clangd.log")?;
 
-        let process = Command::new("clangd")
-            .arg("--log=info")
+        let mut command = Command::new("clangd");
+        command.arg("--log=info");
+        if let Some(compile_commands_dir) = prepare_compile_commands(root_path).await? {
+            command.arg(format!(
+                "--compile-commands-dir={}",
+                compile_commands_dir.display()
+            ));
+        }
+        let process = command
             .current_dir(root_path)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
@@ -133,18 +118,160 @@ impl ClangdClient {
             json_rpc: json_rpc_handler,
             workspace_documents,
             pending_requests,
+            readiness: ServerReadiness::new(),
         })
     }
 }
 
-#[derive(Serialize, Deserialize)]
-struct CompileCommand {
-    directory: String,
-    command: String,
-    file: String,
+/// Returns the directory clangd should read `compile_commands.json` from, `None` when the
+/// workspace has its own `compile_commands.json` or `compile_flags.txt`.
+///
+/// The generated database goes to the lsproxy state directory, the workspace may be read-only
+/// and we don't want to leave files in the user's tree.
+async fn prepare_compile_commands(
+    root_path: &str,
+) -> Result<Option<PathBuf>, Box<dyn Error + Send + Sync>> {
+    let compile_db_files = search_files(
+        Path::new(root_path),
+        vec![
+            String::from("**/compile_commands.json"),
+            String::from("**/compile_flags.txt"),
+        ],
+        vec![String::from("**/.git")],
+        false,
+    )?;
+    if !compile_db_files.is_empty() {
+        // clangd finds them next to the sources on its own
+        return Ok(None);
+    }
+
+    debug!("Couldn't find compile comands json, falling back to generation");
+    // this is a workaround to avoid building the entire project
+    let commands = generate_compile_commands(root_path.to_string())?;
+    let json = serde_json::to_string_pretty(&commands)?;
+
+    let compile_commands_dir = compile_commands_dir(root_path);
+    fs::create_dir_all(&compile_commands_dir).await?;
+    write(compile_commands_dir.join("compile_commands.json"), json).await?;
+
+    debug!(
+        "Generated {:?} with {} entries",
+        compile_commands_dir.join("compile_commands.json"),
+        commands.len()
+    );
+    Ok(Some(compile_commands_dir))
+}
+
+/// One directory per workspace, so that several clangd instances don't share a database.
+fn compile_commands_dir(root_path: &str) -> PathBuf {
+    state_dir()
+        .join("clangd")
+        .join(root_path.trim_matches('/').replace('/', "_"))
+}
+
+/// An entry of a compilation database, either with a `command` string or an `arguments` list.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompileCommand {
+    pub directory: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arguments: Vec<String>,
+    pub file: String,
+}
+
+impl CompileCommand {
+    pub fn command_line(&self) -> String {
+        if self.command.is_empty() {
+            self.arguments.join(" ")
+        } else {
+            self.command.clone()
+        }
+    }
+}
+
+/// Where the compile command clangd uses for a file comes from.
+#[derive(Debug, PartialEq)]
+pub enum CompileCommandSource {
+    /// A `compile_commands.json` in the workspace.
+    Workspace,
+    /// The `compile_flags.txt` closest to the file.
+    CompileFlags,
+    /// Generated by lsproxy from the build files.
+    Generated,
+}
+
+/// Finds the compile command clangd uses for `file_path`, for debugging.
+pub fn find_compile_command(
+    root_path: &Path,
+    file_path: &Path,
+) -> Result<Option<(CompileCommand, CompileCommandSource)>, Box<dyn Error + Send + Sync>> {
+    let find_in_database = |database: &Path| -> Option<CompileCommand> {
+        let content = std::fs::read_to_string(database).ok()?;
+        let commands: Vec<CompileCommand> = serde_json::from_str(&content)
+            .map_err(|e| debug!("Invalid compilation database {:?}: {}", database, e))
+            .ok()?;
+        commands
+            .into_iter()
+            .find(|command| Path::new(&command.directory).join(&command.file) == file_path)
+    };
+
+    let workspace_databases = search_files(
+        root_path,
+        vec![String::from("**/compile_commands.json")],
+        vec![String::from("**/.git")],
+        false,
+    )?;
+    if let Some(command) = workspace_databases
+        .iter()
+        .find_map(|database| find_in_database(database))
+    {
+        return Ok(Some((command, CompileCommandSource::Workspace)));
+    }
+
+    if let Some(flags_file) = file_path
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(root_path))
+        .map(|dir| dir.join("compile_flags.txt"))
+        .find(|flags_file| flags_file.is_file())
+    {
+        let flags = std::fs::read_to_string(&flags_file)?;
+        let directory = flags_file.parent().unwrap_or(root_path);
+        let command = CompileCommand {
+            directory: directory.to_string_lossy().to_string(),
+            command: String::new(),
+            arguments: std::iter::once(compiler_for_file(file_path).to_string())
+                .chain(
+                    flags
+                        .lines()
+                        .map(str::trim)
+                        .filter(|l| !l.is_empty())
+                        .map(String::from),
+                )
+                .chain([String::from("-c"), file_path.to_string_lossy().to_string()])
+                .collect(),
+            file: file_path.to_string_lossy().to_string(),
+        };
+        return Ok(Some((command, CompileCommandSource::CompileFlags)));
+    }
+
+    let generated_database =
+        compile_commands_dir(&root_path.to_string_lossy()).join("compile_commands.json");
+    Ok(find_in_database(&generated_database)
+        .map(|command| (command, CompileCommandSource::Generated)))
+}
+
+/// The driver decides how clangd parses a file, `c++` would treat C sources as C++.
+fn compiler_for_file(file_path: &Path) -> &'static str {
+    match file_path.extension().and_then(|ext| ext.to_str()) {
+        Some("c") => "/usr/bin/cc",
+        _ => "/usr/bin/c++",
+    }
 }
 
-fn find_include_dirs(project_root: &Path, cmakelists_files: &[PathBuf]) -> Vec<String> {
+/// Heuristic include directories, used when the build files don't declare any.
+fn find_include_dirs(project_root: &Path) -> Vec<String> {
     let mut include_dirs = HashSet::new();
 
     // Use search_directories to find all directories (including "include")
@@ -163,13 +290,6 @@ fn find_include_dirs(project_root: &Path, cmakelists_files: &[PathBuf]) -> Vec<S
         }
     }
 
-    // Add directories containing CMakeLists.txt files
-    for cmake_file in cmakelists_files {
-        if let Some(parent_dir) = cmake_file.parent() {
-            include_dirs.insert(parent_dir.to_string_lossy().into_owned());
-        }
-    }
-
     include_dirs.into_iter().collect()
 }
 
@@ -201,86 +321,389 @@ fn generate_compile_commands(
     project_root: String,
 ) -> Result<Vec<CompileCommand>, Box<dyn std::error::Error + Send + Sync>> {
     let project_path = Path::new(&project_root);
+    let exclude_patterns: Vec<String> = DEFAULT_EXCLUDE_PATTERNS
+        .iter()
+        .map(|&s| s.to_string())
+        .collect();
 
     // Find CMakeLists.txt files
     debug!("Finding CMakeLists.txt files...");
     let cmakelists_files = search_files(
         project_path,
         vec!["**/CMakeLists.txt".to_string()],
-        DEFAULT_EXCLUDE_PATTERNS
+        exclude_patterns.clone(),
+        true,
+    )?;
+    let makefiles = search_files(
+        project_path,
+        MAKEFILE_NAMES
             .iter()
-            .map(|&s| s.to_string())
+            .map(|name| format!("**/{}", name))
             .collect(),
+        exclude_patterns,
         true,
     )?;
 
-    // Parse CMakeLists.txt for compiler flags and C++ standard
-    debug!("Parsing CMakeLists.txt files...");
-    let flags = parse_cmakelists(&cmakelists_files);
+    // Parse the build files for include paths, definitions, compiler flags and standards
+    debug!("Parsing CMakeLists.txt files and Makefiles...");
+    let mut flags = InferredFlags::default();
+    parse_cmakelists(project_path, &cmakelists_files, &mut flags);
+    parse_makefiles(&makefiles, &mut flags);
 
-    // Find inferred include directories
-    debug!("Finding inferred include directories...");
-    let include_dirs = find_include_dirs(project_path, &cmakelists_files);
+    let declares_include_dirs = !flags.include_dirs.is_empty();
+
+    // Directories containing CMakeLists.txt files
+    for cmake_file in &cmakelists_files {
+        if let Some(parent_dir) = cmake_file.parent() {
+            flags.add_include_dir(parent_dir);
+        }
+    }
+    if !declares_include_dirs {
+        debug!("No include directories declared, finding inferred include directories...");
+        for dir in find_include_dirs(project_path) {
+            flags.add_include_dir(Path::new(&dir));
+        }
+    }
 
     // Find source files
     debug!("Finding source files...");
     let source_files = find_source_files(project_path);
 
     debug!("Found {} source files", source_files.len());
-    debug!("Using include paths: {:?}", include_dirs);
     debug!("Using compiler flags: {:?}", flags);
 
-    // Generate compile commands
-    let compiler = "/usr/bin/c++";
-    let include_flags: Vec<String> = include_dirs
-        .iter()
-        .map(|inc| format!("-I{}", inc))
-        .collect();
-
+    // Generate compile commands, the driver and the standard depend on the file's language
     let compile_commands = source_files
         .iter()
-        .map(|file| CompileCommand {
-            directory: project_root.clone(),
-            command: format!(
-                "{} {} {} -c {}",
-                compiler,
-                include_flags.join(" "),
-                flags.join(" "),
-                file
-            ),
-            file: file.clone(),
+        .map(|file| {
+            let file_path = Path::new(file);
+            let mut command = vec![compiler_for_file(file_path).to_string()];
+            command.extend(flags.for_file(file_path));
+            command.extend([String::from("-c"), file.clone()]);
+            CompileCommand {
+                directory: project_root.clone(),
+                command: command.join(" "),
+                arguments: Vec::new(),
+                file: file.clone(),
+            }
         })
         .collect();
 
     Ok(compile_commands)
 }
 
-fn parse_cmakelists(cmake_files: &[PathBuf]) -> Vec<String> {
-    let mut flags = Vec::new();
+const MAKEFILE_NAMES: &[&str] = &["Makefile", "makefile", "GNUmakefile"];
+
+/// Compiler flags inferred from the build files of a project.
+#[derive(Debug, Default, PartialEq)]
+struct InferredFlags {
+    include_dirs: Vec<String>,
+    /// `-D` and `-U` flags.
+    definitions: Vec<String>,
+    options: Vec<String>,
+    /// Value of `-std=` for C files, e.g. `c11` or `gnu99`.
+    c_standard: Option<String>,
+    /// Value of `-std=` for C++ files, e.g. `c++17`.
+    cxx_standard: Option<String>,
+}
+
+impl InferredFlags {
+    fn add_include_dir(&mut self, dir: &Path) {
+        let dir = dir.to_string_lossy().to_string();
+        if !self.include_dirs.contains(&dir) {
+            self.include_dirs.push(dir);
+        }
+    }
+
+    fn add_definition(&mut self, definition: String) {
+        if !self.definitions.contains(&definition) {
+            self.definitions.push(definition);
+        }
+    }
+
+    /// Sorts a literal compiler flag into include paths, definitions, standards and other options.
+    fn add_flag(&mut self, flag: &str, base_dir: &Path) {
+        if let Some(dir) = flag.strip_prefix("-I").filter(|dir| !dir.is_empty()) {
+            self.add_include_dir(&base_dir.join(dir));
+        } else if flag.starts_with("-D") || flag.starts_with("-U") {
+            self.add_definition(flag.to_string());
+        } else if let Some(standard) = flag.strip_prefix("-std=") {
+            if standard.contains("++") {
+                self.cxx_standard = Some(standard.to_string());
+            } else {
+                self.c_standard = Some(standard.to_string());
+            }
+        } else if flag.starts_with('-') && !self.options.iter().any(|o| o == flag) {
+            self.options.push(flag.to_string());
+        }
+    }
+
+    fn for_file(&self, file: &Path) -> Vec<String> {
+        let mut flags: Vec<String> = self
+            .include_dirs
+            .iter()
+            .map(|dir| format!("-I{}", dir))
+            .collect();
+        flags.extend(self.definitions.iter().cloned());
+        flags.extend(self.options.iter().cloned());
+        let standard = match file.extension().and_then(|ext| ext.to_str()) {
+            Some("c") => &self.c_standard,
+            _ => &self.cxx_standard,
+        };
+        if let Some(standard) = standard {
+            flags.push(format!("-std={}", standard));
+        }
+        flags
+    }
+}
+
+/// Returns the arguments of every call to the CMake command `name`.
+fn cmake_command_args(content: &str, name: &str) -> Vec<Vec<String>> {
+    regex::Regex::new(&format!(r"(?is)\b{}\s*\((.*?)\)", name))
+        .unwrap()
+        .captures_iter(content)
+        .map(|caps| {
+            caps[1]
+                .split_whitespace()
+                .map(|arg| arg.trim_matches('"').to_string())
+                .collect()
+        })
+        .collect()
+}
+
+fn parse_cmakelists(project_root: &Path, cmake_files: &[PathBuf], flags: &mut InferredFlags) {
+    let comment = regex::Regex::new(r"#.*").unwrap();
     for cmake_path in cmake_files {
-        if let Ok(content) = std::fs::read_to_string(cmake_path) {
-            // Extract C++ standard (this part is fine)
-            if let Some(capture) = regex::Regex::new(r"set\s*\(\s*CMAKE_CXX_STANDARD\s+(\d+)\s*\)")
-                .unwrap()
-                .captures(&content)
+        let Ok(content) = std::fs::read_to_string(cmake_path) else {
+            continue;
+        };
+        let content = comment.replace_all(&content, "");
+        let cmake_dir = cmake_path.parent().unwrap_or(project_root);
+        let expand = |arg: &str| -> Option<String> {
+            let arg = arg
+                .replace("${CMAKE_CURRENT_SOURCE_DIR}", &cmake_dir.to_string_lossy())
+                .replace("${CMAKE_CURRENT_LIST_DIR}", &cmake_dir.to_string_lossy())
+                .replace("${PROJECT_SOURCE_DIR}", &project_root.to_string_lossy())
+                .replace("${CMAKE_SOURCE_DIR}", &project_root.to_string_lossy());
+            // Skip anything we can't resolve, like other variables and generator expressions
+            (!arg.contains("${") && !arg.contains("$<")).then_some(arg)
+        };
+
+        for (variable, standard) in [
+            ("CMAKE_CXX_STANDARD", &mut flags.cxx_standard),
+            ("CMAKE_C_STANDARD", &mut flags.c_standard),
+        ] {
+            if let Some(capture) =
+                regex::Regex::new(&format!(r"set\s*\(\s*{}\s+(\d+)\s*\)", variable))
+                    .unwrap()
+                    .captures(&content)
             {
-                flags.push(format!("-std=c++{}", &capture[1]));
+                let prefix = if variable == "CMAKE_CXX_STANDARD" {
+                    "c++"
+                } else {
+                    "c"
+                };
+                *standard = Some(format!("{}{}", prefix, &capture[1]));
             }
+        }
 
-            // Extract compile options but skip generator expressions and variables
-            for caps in regex::Regex::new(r"add_compile_options\s*\((.*?)\)")
-                .unwrap()
-                .captures_iter(&content)
-            {
-                // Only take literal flags, skip anything with ${...} or $<...>
-                flags.extend(
-                    caps[1]
-                        .split_whitespace()
-                        .filter(|arg| !arg.contains("${") && !arg.contains("$<"))
-                        .map(String::from),
-                );
+        for args in cmake_command_args(&content, "add_compile_options")
+            .into_iter()
+            .chain(cmake_command_args(&content, "add_definitions"))
+        {
+            for arg in args.iter().filter_map(|arg| expand(arg)) {
+                flags.add_flag(&arg, cmake_dir);
+            }
+        }
+
+        const INCLUDE_KEYWORDS: &[&str] = &["SYSTEM", "BEFORE", "AFTER"];
+        const SCOPE_KEYWORDS: &[&str] = &["INTERFACE", "PUBLIC", "PRIVATE"];
+        let include_dirs = cmake_command_args(&content, "include_directories")
+            .into_iter()
+            .flatten()
+            .chain(
+                // The first argument is the target
+                cmake_command_args(&content, "target_include_directories")
+                    .into_iter()
+                    .flat_map(|args| args.into_iter().skip(1)),
+            );
+        for dir in include_dirs {
+            if INCLUDE_KEYWORDS.contains(&dir.as_str()) || SCOPE_KEYWORDS.contains(&dir.as_str()) {
+                continue;
+            }
+            if let Some(dir) = expand(&dir) {
+                flags.add_include_dir(&cmake_dir.join(dir));
+            }
+        }
+
+        for args in cmake_command_args(&content, "target_compile_definitions") {
+            for definition in args.iter().skip(1) {
+                if SCOPE_KEYWORDS.contains(&definition.as_str()) {
+                    continue;
+                }
+                if let Some(definition) = expand(definition) {
+                    let definition = definition.trim_start_matches("-D");
+                    flags.add_definition(format!("-D{}", definition));
+                }
+            }
+        }
+    }
+}
+
+/// Reads the literal `CFLAGS` and `CPPFLAGS` assignments of Makefiles.
+fn parse_makefiles(makefiles: &[PathBuf], flags: &mut InferredFlags) {
+    let assignment =
+        regex::Regex::new(r"(?m)^\s*(?:override\s+)?(?:CFLAGS|CPPFLAGS)\s*[+:?]?=(.*)$").unwrap();
+    for makefile in makefiles {
+        let Ok(content) = std::fs::read_to_string(makefile) else {
+            continue;
+        };
+        let content = content.replace("\\\n", " ");
+        let makefile_dir = makefile.parent().unwrap_or(Path::new("/"));
+        for caps in assignment.captures_iter(&content) {
+            let value = caps[1].split('#').next().unwrap_or_default();
+            for flag in value.split_whitespace() {
+                if !flag.contains("$(") && !flag.contains("${") {
+                    flags.add_flag(flag, makefile_dir);
+                }
             }
         }
     }
-    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_cmakelists() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        let cmakelists = root.join("CMakeLists.txt");
+        std::fs::write(
+            &cmakelists,
+            r#"
+cmake_minimum_required(VERSION 3.10)
+project(firmware C CXX)
+set(CMAKE_C_STANDARD 99)
+set(CMAKE_CXX_STANDARD 17)
+# include_directories(commented/out)
+include_directories(SYSTEM vendor/include ${CMAKE_BINARY_DIR}/generated)
+add_definitions(-DUSE_HAL -DBOARD=2)
+add_compile_options(-Wall $<$<CONFIG:Debug>:-O0>)
+add_executable(firmware main.c)
+target_include_directories(firmware PRIVATE
+    ${CMAKE_CURRENT_SOURCE_DIR}/drivers
+    "config")
+target_compile_definitions(firmware PUBLIC STM32F4 HSE_VALUE=8000000)
+"#,
+        )?;
+
+        let mut flags = InferredFlags::default();
+        parse_cmakelists(root, &[cmakelists], &mut flags);
+        assert_eq!(
+            flags,
+            InferredFlags {
+                include_dirs: vec![
+                    root.join("vendor/include").to_string_lossy().to_string(),
+                    root.join("drivers").to_string_lossy().to_string(),
+                    root.join("config").to_string_lossy().to_string(),
+                ],
+                definitions: vec![
+                    "-DUSE_HAL".to_string(),
+                    "-DBOARD=2".to_string(),
+                    "-DSTM32F4".to_string(),
+                    "-DHSE_VALUE=8000000".to_string(),
+                ],
+                options: vec!["-Wall".to_string()],
+                c_standard: Some("c99".to_string()),
+                cxx_standard: Some("c++17".to_string()),
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compile_commands_per_language() -> Result<(), Box<dyn Error + Send + Sync>> {
+        // The default `.tmp` prefix would be excluded as a hidden directory
+        let dir = tempfile::Builder::new().prefix("project").tempdir()?;
+        let root = dir.path();
+        std::fs::write(
+            root.join("CMakeLists.txt"),
+            "set(CMAKE_C_STANDARD 11)\nset(CMAKE_CXX_STANDARD 20)\n",
+        )?;
+        std::fs::write(root.join("driver.c"), "")?;
+        std::fs::write(root.join("app.cpp"), "")?;
+
+        let commands = generate_compile_commands(root.to_string_lossy().to_string())?;
+        let command_for = |name: &str| {
+            commands
+                .iter()
+                .find(|command| command.file.ends_with(name))
+                .map(|command| command.command.clone())
+                .unwrap_or_default()
+        };
+        let root = root.to_string_lossy();
+        assert_eq!(
+            command_for("driver.c"),
+            format!("/usr/bin/cc -I{root} -std=c11 -c {root}/driver.c")
+        );
+        assert_eq!(
+            command_for("app.cpp"),
+            format!("/usr/bin/c++ -I{root} -std=c++20 -c {root}/app.cpp")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_compile_command_from_compile_flags() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src"))?;
+        std::fs::write(root.join("compile_flags.txt"), "-Iinclude\n-DDEBUG\n")?;
+        let file = root.join("src/main.c");
+        std::fs::write(&file, "")?;
+
+        let (command, source) = find_compile_command(root, &file)?.ok_or("no command found")?;
+        assert_eq!(source, CompileCommandSource::CompileFlags);
+        assert_eq!(command.directory, root.to_string_lossy());
+        assert_eq!(
+            command.command_line(),
+            format!("/usr/bin/cc -Iinclude -DDEBUG -c {}", file.display())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_makefiles() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = tempdir()?;
+        let root = dir.path();
+        let makefile = root.join("Makefile");
+        std::fs::write(
+            &makefile,
+            "CC = arm-none-eabi-gcc\nCFLAGS = -std=gnu11 -Wall \\\n\t-Iinc $(ARCH_FLAGS) # flags\nCPPFLAGS += -DNDEBUG -I../common\nLDFLAGS = -Llib\n",
+        )?;
+
+        let mut flags = InferredFlags::default();
+        parse_makefiles(&[makefile], &mut flags);
+        assert_eq!(
+            flags.include_dirs,
+            vec![
+                root.join("inc").to_string_lossy().to_string(),
+                root.join("../common").to_string_lossy().to_string(),
+            ]
+        );
+        assert_eq!(flags.definitions, vec!["-DNDEBUG"]);
+        assert_eq!(flags.options, vec!["-Wall"]);
+        assert_eq!(flags.c_standard.as_deref(), Some("gnu11"));
+        assert_eq!(
+            flags
+                .for_file(Path::new("main.c"))
+                .last()
+                .map(String::as_str),
+            Some("-std=gnu11")
+        );
+        Ok(())
+    }
 }
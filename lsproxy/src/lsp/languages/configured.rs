@@ -0,0 +1,106 @@
+use std::error::Error;
+use std::path::Path;
+use std::process::Stdio;
+
+use async_trait::async_trait;
+use notify_debouncer_mini::DebouncedEvent;
+use tokio::process::Command;
+use tokio::sync::broadcast::Receiver;
+
+use crate::config::LanguageServerConfig;
+use crate::lsp::{JsonRpcHandler, LspClient, PendingRequests, ProcessHandler, ServerReadiness};
+
+use crate::utils::workspace_documents::{WorkspaceDocumentsHandler, DEFAULT_EXCLUDE_PATTERNS};
+
+/// A language server described by an entry of the lsproxy configuration file.
+pub struct ConfiguredClient {
+    config: LanguageServerConfig,
+    process: ProcessHandler,
+    json_rpc: JsonRpcHandler,
+    workspace_documents: WorkspaceDocumentsHandler,
+    pending_requests: PendingRequests,
+    readiness: ServerReadiness,
+}
+
+#[async_trait]
+impl LspClient for ConfiguredClient {
+    fn get_initialization_options(&mut self) -> Option<serde_json::Value> {
+        self.config.initialization_options.clone()
+    }
+
+    fn get_process(&mut self) -> &mut ProcessHandler {
+        &mut self.process
+    }
+
+    fn get_json_rpc(&mut self) -> &mut JsonRpcHandler {
+        &mut self.json_rpc
+    }
+
+    fn get_root_files(&mut self) -> Vec<String> {
+        if self.config.root_files.is_empty() {
+            vec![".git".to_string()]
+        } else {
+            self.config.root_files.clone()
+        }
+    }
+
+    fn get_language_id(
+        &mut self,
+        _file_path: &str,
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        Ok(self.config.language_id())
+    }
+
+    fn get_workspace_documents(&mut self) -> &mut WorkspaceDocumentsHandler {
+        &mut self.workspace_documents
+    }
+
+    fn get_pending_requests(&mut self) -> &mut PendingRequests {
+        &mut self.pending_requests
+    }
+
+    fn get_readiness(&mut self) -> &mut ServerReadiness {
+        &mut self.readiness
+    }
+}
+
+impl ConfiguredClient {
+    pub async fn new(
+        root_path: &str,
+        watch_events_rx: Receiver<DebouncedEvent>,
+        config: LanguageServerConfig,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let process = Command::new(&config.command)
+            .args(&config.args)
+            .current_dir(root_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start '{}': {}", config.command, e))?;
+
+        let process_handler = ProcessHandler::new(process)
+            .await
+            .map_err(|e| format!("Failed to create ProcessHandler: {}", e))?;
+        let json_rpc_handler = JsonRpcHandler::new();
+        let workspace_documents = WorkspaceDocumentsHandler::new(
+            Path::new(root_path),
+            config.file_patterns(),
+            DEFAULT_EXCLUDE_PATTERNS
+                .iter()
+                .map(|&s| s.to_string())
+                .collect(),
+            watch_events_rx,
+            config.did_open.clone(),
+        );
+
+        Ok(Self {
+            config,
+            process: process_handler,
+            json_rpc: json_rpc_handler,
+            workspace_documents,
+            pending_requests: PendingRequests::new(),
+            readiness: ServerReadiness::new(),
+        })
+    }
+}
@@ -7,7 +7,10 @@ use notify_debouncer_mini::DebouncedEvent;
 use tokio::{process::Command, sync::broadcast::Receiver};
 
 use crate::{
-    lsp::{ExpectedMessageKey, JsonRpcHandler, LspClient, PendingRequests, ProcessHandler},
+    lsp::{
+        ExpectedMessageKey, JsonRpcHandler, LspClient, PendingRequests, ProcessHandler,
+        ServerReadiness,
+    },
     utils::workspace_documents::{
         DidOpenConfiguration, WorkspaceDocumentsHandler, DEFAULT_EXCLUDE_PATTERNS,
         JAVA_FILE_PATTERNS, JAVA_ROOT_FILES,
@@ -19,6 +22,7 @@ pub struct JdtlsClient {
     json_rpc: JsonRpcHandler,
     workspace_documents: WorkspaceDocumentsHandler,
     pending_requests: PendingRequests,
+    readiness: ServerReadiness,
 }
 
 #[async_trait]
@@ -43,6 +47,10 @@ impl LspClient for JdtlsClient {
         &mut self.pending_requests
     }
 
+    fn get_readiness(&mut self) -> &mut ServerReadiness {
+        &mut self.readiness
+    }
+
     async fn initialize(
         &mut self,
         root_path: String,
@@ -133,6 +141,7 @@ impl JdtlsClient {
             json_rpc: json_rpc_handler,
             workspace_documents,
             pending_requests: PendingRequests::new(),
+            readiness: ServerReadiness::new(),
         })
     }
 }
@@ -1,7 +1,18 @@
+mod bash;
 mod clang;
+mod configured;
+mod csharp;
 mod java;
+mod perl;
+mod php;
+mod pyright;
 mod python;
+mod ruby;
 mod rust;
+mod svelte;
 mod typescript;
 
-pub use self::{clang::*, java::*, python::*, rust::*, typescript::*};
+pub use self::{
+    bash::*, clang::*, configured::*, csharp::*, java::*, perl::*, php::*, pyright::*, python::*,
+    ruby::*, rust::*, svelte::*, typescript::*,
+};
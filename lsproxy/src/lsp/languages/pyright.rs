@@ -0,0 +1,128 @@
+use std::{error::Error, path::Path, process::Stdio};
+
+use async_trait::async_trait;
+use notify_debouncer_mini::DebouncedEvent;
+use serde_json::Value;
+use tokio::process::Command;
+use tokio::sync::broadcast::Receiver;
+
+use crate::config::{PythonBackend, PythonConfig};
+use crate::lsp::json_rpc::JsonRpc;
+use crate::lsp::process::Process;
+use crate::lsp::{JsonRpcHandler, LspClient, PendingRequests, ProcessHandler, ServerReadiness};
+
+use crate::utils::workspace_documents::{
+    DidOpenConfiguration, WorkspaceDocumentsHandler, DEFAULT_EXCLUDE_PATTERNS,
+    PYTHON_FILE_PATTERNS, PYTHON_ROOT_FILES,
+};
+
+/// Client for pyright and its fork basedpyright, which share the same protocol.
+pub struct PyrightClient {
+    process: ProcessHandler,
+    json_rpc: JsonRpcHandler,
+    workspace_documents: WorkspaceDocumentsHandler,
+    pending_requests: PendingRequests,
+    readiness: ServerReadiness,
+    initialization_options: Option<Value>,
+    settings: Option<Value>,
+}
+
+#[async_trait]
+impl LspClient for PyrightClient {
+    fn get_process(&mut self) -> &mut ProcessHandler {
+        &mut self.process
+    }
+
+    fn get_json_rpc(&mut self) -> &mut JsonRpcHandler {
+        &mut self.json_rpc
+    }
+
+    fn get_root_files(&mut self) -> Vec<String> {
+        PYTHON_ROOT_FILES.iter().map(|&s| s.to_string()).collect()
+    }
+
+    fn get_initialization_options(&mut self) -> Option<Value> {
+        self.initialization_options.clone()
+    }
+
+    fn get_workspace_documents(&mut self) -> &mut WorkspaceDocumentsHandler {
+        &mut self.workspace_documents
+    }
+
+    fn get_pending_requests(&mut self) -> &mut PendingRequests {
+        &mut self.pending_requests
+    }
+
+    fn get_readiness(&mut self) -> &mut ServerReadiness {
+        &mut self.readiness
+    }
+
+    async fn setup_workspace(
+        &mut self,
+        _root_path: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // We don't advertise `workspace/configuration`, so settings have to be pushed
+        if let Some(settings) = self.settings.clone() {
+            let notification = self.get_json_rpc().create_notification(
+                "workspace/didChangeConfiguration",
+                serde_json::json!({ "settings": settings }),
+            );
+            let message = format!(
+                "Content-Length: {}\r\n\r\n{}",
+                notification.len(),
+                notification
+            );
+            self.get_process().send(&message).await?;
+        }
+        Ok(())
+    }
+}
+
+impl PyrightClient {
+    pub async fn new(
+        root_path: &str,
+        watch_events_rx: Receiver<DebouncedEvent>,
+        config: &PythonConfig,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let command = match config.backend {
+            PythonBackend::BasedPyright => "basedpyright-langserver",
+            _ => "pyright-langserver",
+        };
+        let process = Command::new(command)
+            .arg("--stdio")
+            .current_dir(root_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| Box::new(e) as Box<dyn Error + Send + Sync>)?;
+
+        let process_handler = ProcessHandler::new(process)
+            .await
+            .map_err(|e| format!("Failed to create ProcessHandler: {}", e))?;
+
+        let workspace_documents = WorkspaceDocumentsHandler::new(
+            Path::new(root_path),
+            PYTHON_FILE_PATTERNS
+                .iter()
+                .map(|&s| s.to_string())
+                .collect(),
+            DEFAULT_EXCLUDE_PATTERNS
+                .iter()
+                .map(|&s| s.to_string())
+                .collect(),
+            watch_events_rx,
+            DidOpenConfiguration::Lazy,
+        );
+
+        Ok(Self {
+            process: process_handler,
+            json_rpc: JsonRpcHandler::new(),
+            workspace_documents,
+            pending_requests: PendingRequests::new(),
+            readiness: ServerReadiness::new(),
+            initialization_options: config.initialization_options.clone(),
+            settings: config.settings.clone(),
+        })
+    }
+}
@@ -1,22 +1,33 @@
-use std::{path::Path, process::Stdio};
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+};
 
 use async_trait::async_trait;
+use log::{debug, warn};
 use notify_debouncer_mini::DebouncedEvent;
+use serde_json::Value;
 use tokio::process::Command;
 use tokio::sync::broadcast::Receiver;
 
-use crate::lsp::{JsonRpcHandler, LspClient, PendingRequests, ProcessHandler};
+use crate::lsp::{JsonRpcHandler, LspClient, PendingRequests, ProcessHandler, ServerReadiness};
 
 use crate::utils::workspace_documents::{
     DidOpenConfiguration, WorkspaceDocumentsHandler, DEFAULT_EXCLUDE_PATTERNS,
     PYTHON_FILE_PATTERNS, PYTHON_ROOT_FILES,
 };
 
+/// Virtualenv directories looked up in the workspace root, in order of preference.
+/// poetry (with `in-project = true`) and uv both create `.venv`.
+const VIRTUALENV_DIRS: &[&str] = &[".venv", "venv", "env", ".env"];
+
 pub struct JediClient {
     process: ProcessHandler,
     json_rpc: JsonRpcHandler,
     workspace_documents: WorkspaceDocumentsHandler,
     pending_requests: PendingRequests,
+    readiness: ServerReadiness,
+    initialization_options: Option<Value>,
 }
 
 #[async_trait]
@@ -33,6 +44,10 @@ impl LspClient for JediClient {
         PYTHON_ROOT_FILES.iter().map(|&s| s.to_string()).collect()
     }
 
+    fn get_initialization_options(&mut self) -> Option<Value> {
+        self.initialization_options.clone()
+    }
+
     fn get_workspace_documents(&mut self) -> &mut WorkspaceDocumentsHandler {
         &mut self.workspace_documents
     }
@@ -40,12 +55,17 @@ impl LspClient for JediClient {
     fn get_pending_requests(&mut self) -> &mut PendingRequests {
         &mut self.pending_requests
     }
+
+    fn get_readiness(&mut self) -> &mut ServerReadiness {
+        &mut self.readiness
+    }
 }
 
 impl JediClient {
     pub async fn new(
         root_path: &str,
         watch_events_rx: Receiver<DebouncedEvent>,
+        initialization_options: Option<Value>,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let process = Command::new("jedi-language-server")
             .current_dir(root_path)
@@ -75,11 +95,298 @@ impl JediClient {
 
         let json_rpc_handler = JsonRpcHandler::new();
 
+        let environment = PythonEnvironment::detect(Path::new(root_path));
+        debug!("Detected python environment: {:?}", environment);
+        let initialization_options = match (
+            environment.jedi_initialization_options(),
+            initialization_options,
+        ) {
+            (Some(mut detected), Some(configured)) => {
+                merge_json(&mut detected, configured);
+                Some(detected)
+            }
+            (detected, configured) => configured.or(detected),
+        };
+
         Ok(Self {
             process: process_handler,
             json_rpc: json_rpc_handler,
             workspace_documents,
             pending_requests: PendingRequests::new(),
+            readiness: ServerReadiness::new(),
+            initialization_options,
         })
     }
 }
+
+/// The python environment of a workspace, as far as it can be told from the files in it.
+#[derive(Debug, Default, PartialEq)]
+pub struct PythonEnvironment {
+    /// Interpreter of the virtualenv inside the workspace.
+    pub interpreter: Option<PathBuf>,
+    /// Import roots that are not on the interpreter's path, e.g. `src/`.
+    pub extra_paths: Vec<PathBuf>,
+}
+
+impl PythonEnvironment {
+    pub fn detect(root_path: &Path) -> Self {
+        let pyproject = read_pyproject(root_path);
+        let tool = pyproject.as_ref().and_then(|p| p.get("tool"));
+
+        let mut environment = Self::default();
+        for venv in virtualenv_candidates(root_path, tool) {
+            if !venv.join("pyvenv.cfg").is_file() {
+                continue;
+            }
+            // A virtualenv created on the host links to an interpreter that may not exist here,
+            // its site-packages are still usable as plain import roots.
+            match ["bin/python", "bin/python3"]
+                .iter()
+                .map(|bin| venv.join(bin))
+                .find(|bin| bin.exists())
+            {
+                Some(interpreter) => environment.interpreter = Some(interpreter),
+                None => environment.extra_paths.extend(site_packages(&venv)),
+            }
+            break;
+        }
+
+        let mut extra_paths: Vec<PathBuf> = tool
+            .map(pyproject_source_roots)
+            .unwrap_or_default()
+            .iter()
+            .map(|dir| root_path.join(dir))
+            .collect();
+        // src/ layout, the package directory lives under src/ instead of the root
+        if !root_path.join("src/__init__.py").exists() {
+            extra_paths.push(root_path.join("src"));
+        }
+        for path in extra_paths {
+            let path = path.canonicalize().unwrap_or(path);
+            if path.is_dir() && path != root_path && !environment.extra_paths.contains(&path) {
+                environment.extra_paths.push(path);
+            }
+        }
+        environment
+    }
+
+    /// The `extra_paths` inside the workspace at `root_path`, relative to it.
+    pub fn source_roots(&self, root_path: &Path) -> Vec<PathBuf> {
+        let root_path = root_path
+            .canonicalize()
+            .unwrap_or_else(|_| root_path.to_path_buf());
+        self.extra_paths
+            .iter()
+            .filter_map(|path| path.strip_prefix(&root_path).ok())
+            .map(Path::to_path_buf)
+            .collect()
+    }
+
+    /// Initialization options for jedi-language-server, `None` if nothing was detected.
+    pub fn jedi_initialization_options(&self) -> Option<Value> {
+        let mut workspace = serde_json::Map::new();
+        if let Some(interpreter) = &self.interpreter {
+            workspace.insert(
+                "environmentPath".to_string(),
+                Value::from(interpreter.to_string_lossy()),
+            );
+        }
+        if !self.extra_paths.is_empty() {
+            workspace.insert(
+                "extraPaths".to_string(),
+                self.extra_paths
+                    .iter()
+                    .map(|p| Value::from(p.to_string_lossy()))
+                    .collect(),
+            );
+        }
+        if workspace.is_empty() {
+            None
+        } else {
+            Some(serde_json::json!({ "workspace": workspace }))
+        }
+    }
+}
+
+fn read_pyproject(root_path: &Path) -> Option<toml::Value> {
+    let content = std::fs::read_to_string(root_path.join("pyproject.toml")).ok()?;
+    toml::from_str(&content)
+        .map_err(|e| warn!("Failed to parse pyproject.toml: {}", e))
+        .ok()
+}
+
+/// `[tool.pyright]` venvPath/venv first, then the conventional directory names.
+fn virtualenv_candidates(root_path: &Path, tool: Option<&toml::Value>) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    for checker in ["pyright", "basedpyright"] {
+        let settings = tool.and_then(|t| t.get(checker));
+        if let Some(venv) = settings
+            .and_then(|s| s.get("venv"))
+            .and_then(|v| v.as_str())
+        {
+            let venv_path = settings
+                .and_then(|s| s.get("venvPath"))
+                .and_then(|v| v.as_str())
+                .unwrap_or(".");
+            candidates.push(root_path.join(venv_path).join(venv));
+        }
+    }
+    candidates.extend(VIRTUALENV_DIRS.iter().map(|dir| root_path.join(dir)));
+    candidates
+}
+
+fn site_packages(venv: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(venv.join("lib")) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path().join("site-packages"))
+        .filter(|path| path.is_dir())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Source directories declared by the build and test tools in `[tool]`, relative to the root.
+fn pyproject_source_roots(tool: &toml::Value) -> Vec<String> {
+    let strings = |value: Option<&toml::Value>| -> Vec<String> {
+        match value {
+            Some(toml::Value::String(s)) => vec![s.clone()],
+            Some(toml::Value::Array(values)) => values
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect(),
+            _ => Vec::new(),
+        }
+    };
+
+    let mut roots = Vec::new();
+    for checker in ["pyright", "basedpyright"] {
+        roots.extend(strings(tool.get(checker).and_then(|t| t.get("extraPaths"))));
+    }
+    roots.extend(strings(
+        tool.get("pytest")
+            .and_then(|t| t.get("ini_options"))
+            .and_then(|t| t.get("pythonpath")),
+    ));
+    roots.extend(strings(
+        tool.get("setuptools")
+            .and_then(|t| t.get("packages"))
+            .and_then(|t| t.get("find"))
+            .and_then(|t| t.get("where")),
+    ));
+    if let Some(packages) = tool
+        .get("poetry")
+        .and_then(|t| t.get("packages"))
+        .and_then(|p| p.as_array())
+    {
+        roots.extend(
+            packages
+                .iter()
+                .filter_map(|p| p.get("from").and_then(|f| f.as_str()).map(String::from)),
+        );
+    }
+    roots
+}
+
+/// Recursively merges `overrides` into `base`, values from `overrides` win.
+fn merge_json(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_detect_venv_and_src_layout() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let root = dir.path().canonicalize()?;
+        fs::create_dir_all(root.join(".venv/bin"))?;
+        fs::write(root.join(".venv/pyvenv.cfg"), "home = /usr/bin\n")?;
+        fs::write(root.join(".venv/bin/python"), "")?;
+        fs::create_dir_all(root.join("src/package"))?;
+        fs::write(root.join("src/package/__init__.py"), "")?;
+
+        let environment = PythonEnvironment::detect(&root);
+        assert_eq!(environment.interpreter, Some(root.join(".venv/bin/python")));
+        assert_eq!(environment.extra_paths, vec![root.join("src")]);
+        assert_eq!(environment.source_roots(&root), vec![PathBuf::from("src")]);
+        assert_eq!(
+            environment.jedi_initialization_options(),
+            Some(serde_json::json!({"workspace": {
+                "environmentPath": root.join(".venv/bin/python").to_string_lossy(),
+                "extraPaths": [root.join("src").to_string_lossy()],
+            }}))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_pyproject_settings() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let root = dir.path().canonicalize()?;
+        fs::write(
+            root.join("pyproject.toml"),
+            r#"
+[tool.poetry]
+packages = [{ include = "app", from = "lib" }]
+
+[tool.pytest.ini_options]
+pythonpath = ["tests/helpers", "."]
+
+[tool.pyright]
+venvPath = "envs"
+venv = "dev"
+"#,
+        )?;
+        fs::create_dir_all(root.join("lib/app"))?;
+        fs::create_dir_all(root.join("tests/helpers"))?;
+        // The interpreter of a host-created virtualenv is missing in the container
+        fs::create_dir_all(root.join("envs/dev/lib/python3.11/site-packages"))?;
+        fs::write(root.join("envs/dev/pyvenv.cfg"), "home = /opt/python\n")?;
+
+        let environment = PythonEnvironment::detect(&root);
+        assert_eq!(environment.interpreter, None);
+        assert_eq!(
+            environment.extra_paths,
+            vec![
+                root.join("envs/dev/lib/python3.11/site-packages"),
+                root.join("tests/helpers"),
+                root.join("lib"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_json() {
+        let mut detected = serde_json::json!({"workspace": {"extraPaths": ["/src"], "environmentPath": "/venv/bin/python"}});
+        merge_json(
+            &mut detected,
+            serde_json::json!({"workspace": {"extraPaths": ["/other"]}, "diagnostics": {"enable": false}}),
+        );
+        assert_eq!(
+            detected,
+            serde_json::json!({
+                "workspace": {"extraPaths": ["/other"], "environmentPath": "/venv/bin/python"},
+                "diagnostics": {"enable": false},
+            })
+        );
+    }
+}
@@ -3,14 +3,14 @@ use std::{error::Error, path::Path, process::Stdio};
 use async_trait::async_trait;
 use lsp_types::{
     ClientCapabilities, DocumentSymbolClientCapabilities, InitializeParams,
-    TextDocumentClientCapabilities,
+    TextDocumentClientCapabilities, WindowClientCapabilities,
 };
 use notify_debouncer_mini::DebouncedEvent;
 use tokio::process::Command;
 use tokio::sync::broadcast::Receiver;
 use url::Url;
 
-use crate::lsp::{JsonRpcHandler, LspClient, PendingRequests, ProcessHandler};
+use crate::lsp::{JsonRpcHandler, LspClient, PendingRequests, ProcessHandler, ServerReadiness};
 
 use crate::utils::workspace_documents::{
     DidOpenConfiguration, WorkspaceDocumentsHandler, DEFAULT_EXCLUDE_PATTERNS, RUST_FILE_PATTERNS,
@@ -22,6 +22,7 @@ pub struct RustAnalyzerClient {
     json_rpc: JsonRpcHandler,
     workspace_documents: WorkspaceDocumentsHandler,
     pending_requests: PendingRequests,
+    readiness: ServerReadiness,
 }
 
 #[async_trait]
@@ -35,6 +36,10 @@ impl LspClient for RustAnalyzerClient {
             }),
             ..Default::default()
         });
+        capabilities.window = Some(WindowClientCapabilities {
+            work_done_progress: Some(true),
+            ..Default::default()
+        });
 
         capabilities.experimental = Some(serde_json::json!({
             "serverStatusNotification": true
@@ -80,6 +85,10 @@ impl LspClient for RustAnalyzerClient {
         &mut self.pending_requests
     }
 
+    fn get_readiness(&mut self) -> &mut ServerReadiness {
+        &mut self.readiness
+    }
+
     async fn setup_workspace(
         &mut self,
         _root_path: &str,
@@ -125,6 +134,7 @@ impl RustAnalyzerClient {
             json_rpc: json_rpc_handler,
             workspace_documents,
             pending_requests: PendingRequests::new(),
+            readiness: ServerReadiness::new(),
         })
     }
 }
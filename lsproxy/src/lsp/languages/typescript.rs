@@ -1,25 +1,47 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 
 use async_trait::async_trait;
-use lsp_types::InitializeParams;
+use log::{debug, warn};
+use lsp_types::{
+    GotoDefinitionResponse, InitializeParams, Location, LocationLink, Position, Range,
+    TextDocumentItem,
+};
 use notify_debouncer_mini::DebouncedEvent;
+use regex::Regex;
+use serde_json::Value;
 use tokio::process::Command;
 use tokio::sync::broadcast::Receiver;
 use url::Url;
 
-use crate::lsp::{JsonRpcHandler, LspClient, PendingRequests, ProcessHandler};
+use crate::lsp::{JsonRpcHandler, LspClient, PendingRequests, ProcessHandler, ServerReadiness};
 
+use crate::utils::file_utils::search_files;
 use crate::utils::workspace_documents::{
-    DidOpenConfiguration, WorkspaceDocumentsHandler, DEFAULT_EXCLUDE_PATTERNS,
+    DidOpenConfiguration, WorkspaceDocuments, WorkspaceDocumentsHandler, DEFAULT_EXCLUDE_PATTERNS,
     TYPESCRIPT_AND_JAVASCRIPT_FILE_PATTERNS, TYPESCRIPT_AND_JAVASCRIPT_ROOT_FILES,
 };
 
+/// Extensions of the sources a declaration file can be emitted from, in order of preference.
+const SOURCE_EXTENSIONS: &[&str] = &["ts", "tsx", "mts", "cts", "js", "jsx", "mjs", "cjs"];
+
+/// Where `npm install -g` puts packages when `npm root -g` doesn't tell.
+const DEFAULT_GLOBAL_NODE_MODULES: &str = "/usr/lib/node_modules";
+
+/// Output directories assumed when a package has no `tsconfig.json` declaring them.
+const CONVENTIONAL_OUT_DIRS: &[&str] = &["dist", "lib", "build", "out"];
+
 pub struct TypeScriptLanguageClient {
     process: ProcessHandler,
     json_rpc: JsonRpcHandler,
     workspace_documents: WorkspaceDocumentsHandler,
     pending_requests: PendingRequests,
+    readiness: ServerReadiness,
+    workspace: TypeScriptWorkspace,
+    /// Global packages, tsserver resolves plugins from there.
+    global_node_modules: PathBuf,
 }
 
 #[async_trait]
@@ -43,6 +65,10 @@ impl LspClient for TypeScriptLanguageClient {
         &mut self.pending_requests
     }
 
+    fn get_readiness(&mut self) -> &mut ServerReadiness {
+        &mut self.readiness
+    }
+
     fn get_workspace_documents(&mut self) -> &mut WorkspaceDocumentsHandler {
         &mut self.workspace_documents
     }
@@ -55,11 +81,77 @@ impl LspClient for TypeScriptLanguageClient {
             initialization_options: Some(serde_json::json!({
                 "tsserver": {
                     "useSyntaxServer": "never"
-                }
+                },
+                "plugins": tsserver_plugins(&self.global_node_modules),
             })),
             ..Default::default()
         }
     }
+
+    /// Opens one file of every project, tsserver only loads the projects of open files
+    /// and searches for references within the loaded ones.
+    async fn setup_workspace(
+        &mut self,
+        _root_path: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.workspace.projects.len() < 2 {
+            return Ok(());
+        }
+        let files = self.workspace_documents.list_files().await;
+        let representatives: Vec<PathBuf> = self
+            .workspace
+            .projects
+            .iter()
+            .filter_map(|project| project.representative_file(&files))
+            .collect();
+        debug!(
+            "TypeScript: loading {} projects of the workspace",
+            representatives.len()
+        );
+        for file in representatives {
+            let file_path = file.to_string_lossy().to_string();
+            if self.workspace_documents.is_did_open_document(&file_path) {
+                continue;
+            }
+            let text = self
+                .workspace_documents
+                .read_text_document(&file, None)
+                .await?;
+            let language_id = self.get_language_id(&file_path)?;
+            self.text_document_did_open(TextDocumentItem {
+                uri: Url::from_file_path(&file).map_err(|_| "Invalid file path")?,
+                language_id,
+                version: 1,
+                text,
+            })
+            .await?;
+            self.workspace_documents.add_did_open_document(&file_path);
+        }
+        Ok(())
+    }
+
+    async fn resolve_definition(
+        &mut self,
+        response: GotoDefinitionResponse,
+    ) -> Result<GotoDefinitionResponse, Box<dyn Error + Send + Sync>> {
+        Ok(match response {
+            GotoDefinitionResponse::Scalar(location) => {
+                GotoDefinitionResponse::Scalar(self.workspace.resolve_location(location))
+            }
+            GotoDefinitionResponse::Array(locations) => GotoDefinitionResponse::Array(
+                locations
+                    .into_iter()
+                    .map(|location| self.workspace.resolve_location(location))
+                    .collect(),
+            ),
+            GotoDefinitionResponse::Link(links) => GotoDefinitionResponse::Link(
+                links
+                    .into_iter()
+                    .map(|link| self.workspace.resolve_link(link))
+                    .collect(),
+            ),
+        })
+    }
 }
 
 impl TypeScriptLanguageClient {
@@ -98,6 +190,974 @@ impl TypeScriptLanguageClient {
             json_rpc: json_rpc_handler,
             workspace_documents,
             pending_requests: PendingRequests::new(),
+            readiness: ServerReadiness::new(),
+            workspace: TypeScriptWorkspace::detect(Path::new(root_path)),
+            global_node_modules: global_node_modules().await,
+        })
+    }
+}
+
+/// The directory `npm install -g` installs packages to.
+async fn global_node_modules() -> PathBuf {
+    match Command::new("npm").args(["root", "-g"]).output().await {
+        Ok(output) if output.status.success() => {
+            PathBuf::from(String::from_utf8_lossy(&output.stdout).trim())
+        }
+        _ => {
+            warn!(
+                "Failed to run `npm root -g`, looking for tsserver plugins in {}",
+                DEFAULT_GLOBAL_NODE_MODULES
+            );
+            PathBuf::from(DEFAULT_GLOBAL_NODE_MODULES)
+        }
+    }
+}
+
+/// tsserver plugins for single-file components, those not installed are left out.
+///
+/// The Vue plugin lets tsserver open `.vue` files, the Svelte plugin resolves imports of
+/// `.svelte` components from TypeScript.
+fn tsserver_plugins(node_modules: &Path) -> Vec<Value> {
+    [
+        (
+            "@vue/typescript-plugin",
+            serde_json::json!({ "languages": ["vue"] }),
+        ),
+        ("typescript-svelte-plugin", serde_json::json!({})),
+    ]
+    .into_iter()
+    .filter(|(name, _)| node_modules.join(name).is_dir())
+    .map(|(name, mut plugin)| {
+        plugin["name"] = Value::from(name);
+        plugin["location"] = Value::from(node_modules.to_string_lossy());
+        plugin
+    })
+    .collect()
+}
+
+/// A `tsconfig.json` of the workspace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeScriptProject {
+    pub config_path: PathBuf,
+    /// Directory the sources are emitted from, `compilerOptions.rootDir`.
+    pub root_dir: PathBuf,
+    /// `compilerOptions.outDir` and `compilerOptions.declarationDir`.
+    pub out_dirs: Vec<PathBuf>,
+    /// Configs of the referenced projects.
+    pub references: Vec<PathBuf>,
+}
+
+impl TypeScriptProject {
+    fn load(config_path: &Path) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let dir = config_path.parent().unwrap_or(Path::new("/"));
+        let config = read_json5(config_path)?;
+        let options = compiler_options(config_path, &config, 0);
+
+        let option_dir = |name: &str| options.get(name).map(|(value, base)| base.join(value));
+        let root_dir = option_dir("rootDir").unwrap_or_else(|| dir.to_path_buf());
+        let out_dirs: Vec<PathBuf> = ["outDir", "declarationDir"]
+            .iter()
+            .filter_map(|name| option_dir(name))
+            .collect();
+        let references: Vec<PathBuf> = config
+            .get("references")
+            .and_then(Value::as_array)
+            .map(|references| {
+                references
+                    .iter()
+                    .filter_map(|reference| reference.get("path").and_then(Value::as_str))
+                    .map(|path| {
+                        let path = dir.join(path);
+                        if path.extension().is_some_and(|ext| ext == "json") {
+                            path
+                        } else {
+                            path.join("tsconfig.json")
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            config_path: config_path.to_path_buf(),
+            root_dir: normalize(&root_dir),
+            out_dirs: out_dirs.iter().map(|dir| normalize(dir)).collect(),
+            references: references.iter().map(|path| normalize(path)).collect(),
+        })
+    }
+
+    fn dir(&self) -> &Path {
+        self.config_path.parent().unwrap_or(Path::new("/"))
+    }
+
+    /// The first source file of the project, opening it makes tsserver load the project.
+    fn representative_file(&self, files: &[PathBuf]) -> Option<PathBuf> {
+        files
+            .iter()
+            .filter(|file| file.starts_with(&self.root_dir) || file.starts_with(self.dir()))
+            .filter(|file| !self.out_dirs.iter().any(|dir| file.starts_with(dir)))
+            .min_by_key(|file| (!file.starts_with(&self.root_dir), file.components().count()))
+            .cloned()
+    }
+}
+
+/// The packages and TypeScript projects of a monorepo.
+///
+/// Packages of yarn, npm and pnpm workspaces import each other through symlinks in
+/// `node_modules`, resolved to the declarations their `package.json` points at. tsserver
+/// answers definitions with those declarations, this maps them back to the sources.
+#[derive(Debug, Default)]
+pub struct TypeScriptWorkspace {
+    root: PathBuf,
+    /// Directories of the workspace packages, by package name.
+    pub packages: HashMap<String, PathBuf>,
+    pub projects: Vec<TypeScriptProject>,
+}
+
+impl TypeScriptWorkspace {
+    pub fn detect(root_path: &Path) -> Self {
+        let root = root_path
+            .canonicalize()
+            .unwrap_or_else(|_| root_path.to_path_buf());
+        let mut workspace = Self {
+            packages: find_workspace_packages(&root),
+            projects: find_projects(&root),
+            root,
+        };
+        // Referenced projects are often excluded from the search, e.g. under a hidden folder
+        let mut i = 0;
+        while i < workspace.projects.len() {
+            for reference in workspace.projects[i].references.clone() {
+                if reference.starts_with(&workspace.root)
+                    && !workspace
+                        .projects
+                        .iter()
+                        .any(|project| project.config_path == reference)
+                {
+                    match TypeScriptProject::load(&reference) {
+                        Ok(project) => workspace.projects.push(project),
+                        Err(e) => warn!("Failed to load referenced project {:?}: {}", reference, e),
+                    }
+                }
+            }
+            i += 1;
+        }
+        debug!(
+            "TypeScript workspace: {} packages, {} projects",
+            workspace.packages.len(),
+            workspace.projects.len()
+        );
+        workspace
+    }
+
+    /// Maps a file of the workspace, or a symlink to it, to its source.
+    ///
+    /// Returns `None` when the file is not part of the workspace or its source is not found.
+    pub fn source_file(&self, path: &Path) -> Option<PathBuf> {
+        let resolved = path.canonicalize().ok()?;
+        if !resolved.starts_with(&self.root) || is_in_node_modules(&self.root, &resolved) {
+            return None;
+        }
+        let Some(stem) = declaration_stem(&resolved) else {
+            // A source reached through a symlink in node_modules
+            return (resolved != path).then_some(resolved);
+        };
+
+        let mut candidates: Vec<PathBuf> = self
+            .projects
+            .iter()
+            .flat_map(|project| {
+                project.out_dirs.iter().filter_map(|out_dir| {
+                    stem.strip_prefix(out_dir)
+                        .ok()
+                        .map(|relative| project.root_dir.join(relative))
+                })
+            })
+            .collect();
+        if let Some(package_dir) = self.package_dir(&resolved) {
+            if let Ok(relative) = stem.strip_prefix(package_dir) {
+                let mut components = relative.components();
+                if components.next().is_some_and(|first| {
+                    CONVENTIONAL_OUT_DIRS
+                        .iter()
+                        .any(|dir| first.as_os_str() == *dir)
+                }) {
+                    let rest = components.as_path();
+                    candidates.push(package_dir.join("src").join(rest));
+                    candidates.push(package_dir.join(rest));
+                }
+            }
+        }
+        // Declarations written next to their sources
+        candidates.push(stem.clone());
+
+        candidates.iter().find_map(|candidate| {
+            SOURCE_EXTENSIONS
+                .iter()
+                .map(|ext| with_added_extension(candidate, ext))
+                .find(|source| source.is_file())
+        })
+    }
+
+    fn package_dir(&self, path: &Path) -> Option<&PathBuf> {
+        self.packages
+            .values()
+            .filter(|dir| path.starts_with(dir))
+            .max_by_key(|dir| dir.components().count())
+    }
+
+    /// Maps a location in a declaration file to the declaration in its source, with the
+    /// declaration map emitted next to it if any, otherwise by looking for the declared name.
+    ///
+    /// Returns `None` when the declaration isn't found, the location is kept then.
+    fn resolve_range(&self, uri: &Url, range: Range) -> Option<(Url, Range)> {
+        let path = uri.to_file_path().ok()?;
+        let resolved = path.canonicalize().ok()?;
+        if declaration_stem(&resolved).is_some() {
+            if let Some(mapped) = self.resolve_with_declaration_map(&resolved, range.start) {
+                return Some(mapped);
+            }
+        }
+        let source = self.source_file(&path)?;
+        let source_uri = Url::from_file_path(&source).ok()?;
+        if declaration_stem(&resolved).is_none() {
+            // Same file, only the path changed
+            return Some((source_uri, range));
+        }
+        let name = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| identifier_at(&content, range.start))?;
+        let content = std::fs::read_to_string(&source).ok()?;
+        let start = find_declaration(&content, &name)?;
+        let end = Position {
+            line: start.line,
+            character: start.character + name.encode_utf16().count() as u32,
+        };
+        debug!("Resolved definition {:?} to {:?}", path, source);
+        Some((source_uri, Range { start, end }))
+    }
+
+    /// Follows the `.d.ts.map` of a declaration file to a source of the workspace.
+    fn resolve_with_declaration_map(
+        &self,
+        declaration: &Path,
+        position: Position,
+    ) -> Option<(Url, Range)> {
+        let (source, start) = map_declaration(declaration, position)?;
+        let source = source.canonicalize().ok()?;
+        if !source.starts_with(&self.root) || is_in_node_modules(&self.root, &source) {
+            return None;
+        }
+        let name = std::fs::read_to_string(&source)
+            .ok()
+            .and_then(|content| identifier_at(&content, start))
+            .unwrap_or_default();
+        let end = Position {
+            line: start.line,
+            character: start.character + name.encode_utf16().count() as u32,
+        };
+        debug!(
+            "Resolved definition {:?} to {:?} with its declaration map",
+            declaration, source
+        );
+        Some((Url::from_file_path(&source).ok()?, Range { start, end }))
+    }
+
+    pub fn resolve_location(&self, location: Location) -> Location {
+        match self.resolve_range(&location.uri, location.range) {
+            Some((uri, range)) => Location { uri, range },
+            None => location,
+        }
+    }
+
+    pub fn resolve_link(&self, link: LocationLink) -> LocationLink {
+        match self.resolve_range(&link.target_uri, link.target_selection_range) {
+            Some((uri, range)) => LocationLink {
+                target_uri: uri,
+                target_range: range,
+                target_selection_range: range,
+                ..link
+            },
+            None => link,
+        }
+    }
+}
+
+/// Returns the directories of the workspace packages, by package name.
+///
+/// Reads `workspaces` of the root `package.json` (npm, yarn), `pnpm-workspace.yaml`
+/// and `lerna.json`.
+fn find_workspace_packages(root: &Path) -> HashMap<String, PathBuf> {
+    let mut patterns: Vec<String> = Vec::new();
+    if let Ok(package) = read_json5(&root.join("package.json")) {
+        let workspaces = package.get("workspaces");
+        let list = workspaces
+            .and_then(|w| w.get("packages"))
+            .or(workspaces)
+            .and_then(Value::as_array);
+        patterns.extend(
+            list.into_iter()
+                .flatten()
+                .filter_map(|p| p.as_str().map(String::from)),
+        );
+    }
+    if let Ok(content) = std::fs::read_to_string(root.join("pnpm-workspace.yaml")) {
+        match serde_yaml::from_str::<serde_yaml::Value>(&content) {
+            Ok(pnpm) => patterns.extend(
+                pnpm.get("packages")
+                    .and_then(|p| p.as_sequence())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|p| p.as_str().map(String::from)),
+            ),
+            Err(e) => warn!("Failed to parse pnpm-workspace.yaml: {}", e),
+        }
+    }
+    if let Ok(lerna) = read_json5(&root.join("lerna.json")) {
+        patterns.extend(
+            lerna
+                .get("packages")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|p| p.as_str().map(String::from)),
+        );
+    }
+
+    let (excludes, includes): (Vec<_>, Vec<_>) = patterns
+        .iter()
+        .partition(|pattern| pattern.starts_with('!'));
+    let excludes: Vec<glob::Pattern> = excludes
+        .iter()
+        .filter_map(|pattern| glob::Pattern::new(&root.join(&pattern[1..]).to_string_lossy()).ok())
+        .collect();
+
+    let mut packages = HashMap::new();
+    for pattern in includes {
+        let pattern = root.join(pattern.trim_end_matches('/'));
+        let Ok(paths) = glob::glob(&pattern.to_string_lossy()) else {
+            warn!("Invalid workspace pattern: {:?}", pattern);
+            continue;
+        };
+        for dir in paths.flatten() {
+            if excludes.iter().any(|exclude| exclude.matches_path(&dir))
+                || is_in_node_modules(root, &dir)
+            {
+                continue;
+            }
+            let name = read_json5(&dir.join("package.json"))
+                .ok()
+                .and_then(|package| package.get("name")?.as_str().map(String::from));
+            if let Some(name) = name {
+                packages.insert(name, normalize(&dir));
+            }
+        }
+    }
+    packages
+}
+
+fn find_projects(root: &Path) -> Vec<TypeScriptProject> {
+    let configs = search_files(
+        root,
+        vec!["**/tsconfig.json".to_string()],
+        DEFAULT_EXCLUDE_PATTERNS
+            .iter()
+            .map(|&s| s.to_string())
+            .collect(),
+        true,
+    )
+    .unwrap_or_default();
+    let mut projects: Vec<TypeScriptProject> = configs
+        .iter()
+        .filter_map(|config| {
+            TypeScriptProject::load(config)
+                .map_err(|e| warn!("Failed to load {:?}: {}", config, e))
+                .ok()
         })
+        .collect();
+    projects.sort_by(|a, b| a.config_path.cmp(&b.config_path));
+    projects
+}
+
+/// Returns the `compilerOptions` paths of a config and its `extends` chain, with the
+/// directory they are relative to.
+fn compiler_options(
+    config_path: &Path,
+    config: &Value,
+    depth: usize,
+) -> HashMap<String, (String, PathBuf)> {
+    let dir = config_path.parent().unwrap_or(Path::new("/"));
+    let mut options = HashMap::new();
+    if depth < 8 {
+        // Packages in `extends` are resolved by node, only relative paths are followed
+        if let Some(base) = config
+            .get("extends")
+            .and_then(Value::as_str)
+            .filter(|base| base.starts_with('.'))
+        {
+            let mut base_path = dir.join(base);
+            if base_path.extension().is_none() {
+                base_path.set_extension("json");
+            }
+            if let Ok(base_config) = read_json5(&base_path) {
+                options = compiler_options(&base_path, &base_config, depth + 1);
+            }
+        }
+    }
+    if let Some(compiler_options) = config.get("compilerOptions").and_then(Value::as_object) {
+        for name in ["rootDir", "outDir", "declarationDir"] {
+            if let Some(value) = compiler_options.get(name).and_then(Value::as_str) {
+                options.insert(name.to_string(), (value.to_string(), dir.to_path_buf()));
+            }
+        }
+    }
+    options
+}
+
+/// tsconfig and package.json files allow comments and trailing commas.
+fn read_json5(path: &Path) -> Result<Value, Box<dyn Error + Send + Sync>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(json5::from_str(&content)?)
+}
+
+/// Resolves `.` and `..` without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn is_in_node_modules(root: &Path, path: &Path) -> bool {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .any(|component| component.as_os_str() == "node_modules")
+}
+
+/// Strips the extension of a declaration file, `dist/index.d.ts` becomes `dist/index`.
+fn declaration_stem(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_str()?;
+    [".d.ts", ".d.mts", ".d.cts"]
+        .iter()
+        .find_map(|ext| file_name.strip_suffix(ext))
+        .map(|stem| path.with_file_name(stem))
+}
+
+fn with_added_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+fn identifier_at(content: &str, position: Position) -> Option<String> {
+    let line = content.lines().nth(position.line as usize)?;
+    let start = line
+        .char_indices()
+        .scan(0, |utf16, (index, c)| {
+            let current = *utf16;
+            *utf16 += c.len_utf16() as u32;
+            Some((current, index))
+        })
+        .find(|(utf16, _)| *utf16 >= position.character)
+        .map(|(_, index)| index)?;
+    let name: String = line[start..]
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+        .collect();
+    (!name.is_empty()).then_some(name)
+}
+
+/// Maps `position` in a declaration file to its source, with the `.d.ts.map` that
+/// `declarationMap` emits next to it.
+fn map_declaration(declaration: &Path, position: Position) -> Option<(PathBuf, Position)> {
+    let content = std::fs::read_to_string(with_added_extension(declaration, "map")).ok()?;
+    let map: Value = serde_json::from_str(&content).ok()?;
+    let (source, line, character) = source_map_segment(map.get("mappings")?.as_str()?, position)?;
+    let source_root = map
+        .get("sourceRoot")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let source = map.get("sources")?.as_array()?.get(source)?.as_str()?;
+    let dir = declaration.parent()?;
+    Some((
+        normalize(&dir.join(source_root).join(source)),
+        Position { line, character },
+    ))
+}
+
+/// Finds the source index, line and column of the last segment of `mappings` starting at or
+/// before `position` on its line.
+fn source_map_segment(mappings: &str, position: Position) -> Option<(usize, u32, u32)> {
+    // Everything but the generated column is relative to the previous segment of any line
+    let (mut source, mut source_line, mut source_column) = (0i64, 0i64, 0i64);
+    let mut found = None;
+    for (line, segments) in mappings.split(';').enumerate() {
+        let mut column = 0i64;
+        for segment in segments.split(',').filter(|segment| !segment.is_empty()) {
+            let fields = decode_vlq(segment)?;
+            column += fields[0];
+            if fields.len() < 4 {
+                continue;
+            }
+            source += fields[1];
+            source_line += fields[2];
+            source_column += fields[3];
+            if line == position.line as usize && column <= position.character as i64 {
+                found = Some((
+                    usize::try_from(source).ok()?,
+                    u32::try_from(source_line).ok()?,
+                    u32::try_from(source_column).ok()?,
+                ));
+            }
+        }
+        if line == position.line as usize {
+            break;
+        }
+    }
+    found
+}
+
+/// Decodes the base64 VLQ fields of a source map segment.
+fn decode_vlq(segment: &str) -> Option<Vec<i64>> {
+    const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut fields = Vec::new();
+    let (mut value, mut shift) = (0i64, 0);
+    for byte in segment.bytes() {
+        let digit = BASE64.iter().position(|&b| b == byte)? as i64;
+        value += (digit & 31) << shift;
+        if digit & 32 != 0 {
+            shift += 5;
+            continue;
+        }
+        fields.push(if value & 1 == 1 {
+            -(value >> 1)
+        } else {
+            value >> 1
+        });
+        (value, shift) = (0, 0);
+    }
+    (!fields.is_empty()).then_some(fields)
+}
+
+/// Blanks out comments and the contents of string and template literals, keeping the lines and
+/// the UTF-16 columns of everything else.
+fn mask_comments_and_strings(content: &str) -> String {
+    let mut masked = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let blank = |masked: &mut String, c: char| {
+        if c == '\n' {
+            masked.push('\n');
+        } else {
+            masked.extend(std::iter::repeat_n(' ', c.len_utf16()));
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                blank(&mut masked, c);
+                while let Some(&next) = chars.peek() {
+                    if next == '\n' {
+                        break;
+                    }
+                    blank(&mut masked, next);
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                blank(&mut masked, c);
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    blank(&mut masked, next);
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            '"' | '\'' | '`' => {
+                masked.push(c);
+                while let Some(next) = chars.next() {
+                    if next == c {
+                        masked.push(next);
+                        break;
+                    }
+                    blank(&mut masked, next);
+                    if next == '\\' {
+                        if let Some(escaped) = chars.next() {
+                            blank(&mut masked, escaped);
+                        }
+                    }
+                }
+            }
+            c => masked.push(c),
+        }
+    }
+    masked
+}
+
+/// Finds the declaration of `name` in a source file, outside of comments and strings.
+///
+/// Declarations with a keyword win over class members, which win over any occurrence.
+fn find_declaration(content: &str, name: &str) -> Option<Position> {
+    let content = mask_comments_and_strings(content);
+    let name = regex::escape(name);
+    let patterns = [
+        format!(
+            r"\b(?:function\*?|class|interface|type|enum|const|let|var|namespace|module)\s+({})\b",
+            name
+        ),
+        format!(
+            r"^\s*(?:(?:public|private|protected|static|readonly|async|abstract|override|declare|get|set)\s+)*\*?({})\s*[?!]?\s*[(<:=]",
+            name
+        ),
+        format!(r"(?:^|[^\w$])({})(?:[^\w$]|$)", name),
+    ];
+    patterns.iter().find_map(|pattern| {
+        let regex = Regex::new(pattern).ok()?;
+        content.lines().enumerate().find_map(|(line, text)| {
+            regex
+                .captures(text)
+                .and_then(|captures| captures.get(1))
+                .map(|m| Position {
+                    line: line as u32,
+                    character: text[..m.start()].encode_utf16().count() as u32,
+                })
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write(root: &Path, path: &str, content: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    fn monorepo() -> Result<tempfile::TempDir, Box<dyn Error + Send + Sync>> {
+        let dir = tempfile::Builder::new().prefix("project").tempdir()?;
+        let root = dir.path();
+        write(
+            root,
+            "package.json",
+            r#"{"private": true, "workspaces": ["packages/*", "!packages/ignored"]}"#,
+        )?;
+        write(
+            root,
+            "tsconfig.base.json",
+            r#"{
+                // shared options
+                "compilerOptions": {"composite": true, "declaration": true,},
+            }"#,
+        )?;
+        write(
+            root,
+            "tsconfig.json",
+            r#"{"files": [], "references": [{"path": "./packages/core"}, {"path": "./packages/app"}]}"#,
+        )?;
+        write(
+            root,
+            "packages/core/package.json",
+            r#"{"name": "@acme/core", "types": "dist/index.d.ts"}"#,
+        )?;
+        write(
+            root,
+            "packages/core/tsconfig.json",
+            r#"{"extends": "../../tsconfig.base.json", "compilerOptions": {"rootDir": "src", "outDir": "dist"}}"#,
+        )?;
+        write(
+            root,
+            "packages/core/src/index.ts",
+            "import { helper } from './util';
+
+export function greet(name: string): string {
+    return helper(name);
+}
+",
+        )?;
+        write(
+            root,
+            "packages/core/dist/index.d.ts",
+            "export declare function greet(name: string): string;
+",
+        )?;
+        write(
+            root,
+            "packages/app/package.json",
+            r#"{"name": "@acme/app", "dependencies": {"@acme/core": "*"}}"#,
+        )?;
+        write(
+            root,
+            "packages/app/tsconfig.json",
+            r#"{"compilerOptions": {"outDir": "build"}, "references": [{"path": "../core"}]}"#,
+        )?;
+        write(
+            root,
+            "packages/app/main.ts",
+            "import { greet } from '@acme/core';
+greet('world');
+",
+        )?;
+        write(
+            root,
+            "packages/ignored/package.json",
+            r#"{"name": "ignored"}"#,
+        )?;
+        fs::create_dir_all(root.join("node_modules/@acme"))?;
+        std::os::unix::fs::symlink(
+            root.join("packages/core"),
+            root.join("node_modules/@acme/core"),
+        )?;
+        Ok(dir)
+    }
+
+    #[test]
+    fn test_detect_workspace() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = monorepo()?;
+        let root = dir.path().canonicalize()?;
+        let workspace = TypeScriptWorkspace::detect(&root);
+
+        assert_eq!(workspace.packages.len(), 2);
+        assert_eq!(workspace.packages["@acme/core"], root.join("packages/core"));
+        assert_eq!(workspace.packages["@acme/app"], root.join("packages/app"));
+
+        assert_eq!(workspace.projects.len(), 3);
+        let core = workspace
+            .projects
+            .iter()
+            .find(|project| project.config_path == root.join("packages/core/tsconfig.json"))
+            .ok_or("core project not found")?;
+        assert_eq!(core.root_dir, root.join("packages/core/src"));
+        assert_eq!(core.out_dirs, vec![root.join("packages/core/dist")]);
+        let app = workspace
+            .projects
+            .iter()
+            .find(|project| project.config_path == root.join("packages/app/tsconfig.json"))
+            .ok_or("app project not found")?;
+        assert_eq!(
+            app.references,
+            vec![root.join("packages/core/tsconfig.json")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_pnpm_workspace() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = tempfile::Builder::new().prefix("project").tempdir()?;
+        let root = dir.path().canonicalize()?;
+        write(
+            &root,
+            "pnpm-workspace.yaml",
+            "packages:
+  - 'libs/**'
+",
+        )?;
+        write(&root, "libs/a/package.json", r#"{"name": "a"}"#)?;
+        write(&root, "libs/nested/b/package.json", r#"{"name": "b"}"#)?;
+
+        let packages = find_workspace_packages(&root);
+        assert_eq!(packages["a"], root.join("libs/a"));
+        assert_eq!(packages["b"], root.join("libs/nested/b"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_declaration_to_source() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = monorepo()?;
+        let root = dir.path().canonicalize()?;
+        let workspace = TypeScriptWorkspace::detect(&root);
+
+        // Through the symlink in node_modules, as tsserver reports it
+        let declaration = root.join("node_modules/@acme/core/dist/index.d.ts");
+        let location = workspace.resolve_location(Location {
+            uri: Url::from_file_path(&declaration).unwrap(),
+            range: Range {
+                start: Position::new(0, 24),
+                end: Position::new(0, 29),
+            },
+        });
+        assert_eq!(
+            location.uri,
+            Url::from_file_path(root.join("packages/core/src/index.ts")).unwrap()
+        );
+        assert_eq!(location.range.start, Position::new(2, 16));
+        assert_eq!(location.range.end, Position::new(2, 21));
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_with_declaration_map() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = monorepo()?;
+        let root = dir.path().canonicalize()?;
+        write(
+            &root,
+            "packages/core/src/greet.ts",
+            "// Re-exported by index.ts
+
+export const greet = (name: string) => name;
+",
+        )?;
+        // 0:24 of the declaration maps to 2:13 of the first source
+        write(
+            &root,
+            "packages/core/dist/index.d.ts.map",
+            r#"{"version": 3, "file": "index.d.ts", "sourceRoot": "", "sources": ["../src/greet.ts"], "names": [], "mappings": "wBAEa"}"#,
+        )?;
+        let workspace = TypeScriptWorkspace::detect(&root);
+
+        let location = workspace.resolve_location(Location {
+            uri: Url::from_file_path(root.join("node_modules/@acme/core/dist/index.d.ts")).unwrap(),
+            range: Range {
+                start: Position::new(0, 24),
+                end: Position::new(0, 29),
+            },
+        });
+        assert_eq!(
+            location.uri,
+            Url::from_file_path(root.join("packages/core/src/greet.ts")).unwrap()
+        );
+        assert_eq!(location.range.start, Position::new(2, 13));
+        assert_eq!(location.range.end, Position::new(2, 18));
+        Ok(())
+    }
+
+    #[test]
+    fn test_keep_unresolved_declaration() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = monorepo()?;
+        let root = dir.path().canonicalize()?;
+        write(
+            &root,
+            "packages/core/dist/index.d.ts",
+            "export declare function missing(): void;
+",
+        )?;
+        let workspace = TypeScriptWorkspace::detect(&root);
+
+        let location = Location {
+            uri: Url::from_file_path(root.join("node_modules/@acme/core/dist/index.d.ts")).unwrap(),
+            range: Range {
+                start: Position::new(0, 24),
+                end: Position::new(0, 31),
+            },
+        };
+        assert_eq!(workspace.resolve_location(location.clone()), location);
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_symlinked_source() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = monorepo()?;
+        let root = dir.path().canonicalize()?;
+        let workspace = TypeScriptWorkspace::detect(&root);
+
+        let range = Range {
+            start: Position::new(2, 16),
+            end: Position::new(2, 21),
+        };
+        let location = workspace.resolve_location(Location {
+            uri: Url::from_file_path(root.join("node_modules/@acme/core/src/index.ts")).unwrap(),
+            range,
+        });
+        assert_eq!(
+            location.uri,
+            Url::from_file_path(root.join("packages/core/src/index.ts")).unwrap()
+        );
+        assert_eq!(location.range, range);
+
+        // Sources of the workspace are left alone
+        let source = Location {
+            uri: Url::from_file_path(root.join("packages/app/main.ts")).unwrap(),
+            range,
+        };
+        assert_eq!(workspace.resolve_location(source.clone()), source);
+        Ok(())
+    }
+
+    #[test]
+    fn test_representative_file() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = monorepo()?;
+        let root = dir.path().canonicalize()?;
+        let workspace = TypeScriptWorkspace::detect(&root);
+        let files = vec![
+            root.join("packages/core/dist/index.d.ts"),
+            root.join("packages/core/src/index.ts"),
+            root.join("packages/app/main.ts"),
+        ];
+        let core = workspace
+            .projects
+            .iter()
+            .find(|project| project.config_path == root.join("packages/core/tsconfig.json"))
+            .ok_or("core project not found")?;
+        assert_eq!(
+            core.representative_file(&files),
+            Some(root.join("packages/core/src/index.ts"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_tsserver_plugins() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let dir = tempfile::tempdir()?;
+        assert!(tsserver_plugins(dir.path()).is_empty());
+
+        fs::create_dir_all(dir.path().join("@vue/typescript-plugin"))?;
+        assert_eq!(
+            tsserver_plugins(dir.path()),
+            vec![serde_json::json!({
+                "name": "@vue/typescript-plugin",
+                "location": dir.path().to_string_lossy(),
+                "languages": ["vue"],
+            })]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_declaration() {
+        let content = "const x = greet();
+class A {
+    private greet(): void {}
+}
+";
+        assert_eq!(
+            find_declaration(content, "greet"),
+            Some(Position::new(2, 12))
+        );
+        assert_eq!(find_declaration(content, "A"), Some(Position::new(1, 6)));
+        assert_eq!(find_declaration(content, "x"), Some(Position::new(0, 6)));
+        assert_eq!(find_declaration(content, "missing"), None);
+    }
+
+    #[test]
+    fn test_find_declaration_skips_comments_and_strings() {
+        let content = "/* function greet() */
+const s = 'function greet()', t = `class greet ${1}`; // function greet
+export function greet(): void {}
+";
+        assert_eq!(
+            find_declaration(content, "greet"),
+            Some(Position::new(2, 16))
+        );
+        assert_eq!(find_declaration("// const only = 1;\n", "only"), None);
+    }
+
+    #[test]
+    fn test_decode_vlq() {
+        assert_eq!(decode_vlq("wBAEa"), Some(vec![24, 0, 2, 13]));
+        assert_eq!(decode_vlq("D"), Some(vec![-1]));
+        assert_eq!(decode_vlq("!"), None);
     }
 }
@@ -3,4 +3,5 @@ pub(crate) mod json_rpc;
 pub(crate) mod languages;
 pub(crate) mod manager;
 pub(crate) mod process;
-pub use self::{client::*, json_rpc::*, process::*};
+pub(crate) mod readiness;
+pub use self::{client::*, json_rpc::*, process::*, readiness::*};
@@ -1,5 +1,6 @@
 use std::error::Error;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, ChildStdin, ChildStdout};
 use tokio::sync::Mutex;
@@ -14,6 +15,8 @@ pub trait Process: Send + Sync {
 pub struct ProcessHandler {
     pub stdin: Arc<Mutex<ChildStdin>>,
     pub stdout: Arc<Mutex<BufReader<ChildStdout>>>,
+    pub pid: Option<u32>,
+    pub started_at: Instant,
 }
 
 impl ProcessHandler {
@@ -23,6 +26,8 @@ impl ProcessHandler {
         Ok(Self {
             stdin: Arc::new(Mutex::new(stdin)),
             stdout: Arc::new(Mutex::new(BufReader::new(stdout))),
+            pid: child.id(),
+            started_at: Instant::now(),
         })
     }
 }
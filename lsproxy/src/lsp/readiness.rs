@@ -0,0 +1,420 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::{debug, warn};
+use serde_json::Value;
+use tokio::sync::watch;
+
+use crate::lsp::JsonRpcMessage;
+
+/// Tracks whether a language server is done indexing the workspace.
+///
+/// Servers report their background work in different ways:
+/// - `$/progress` work done tokens, announced with `window/workDoneProgress/create`
+/// - `experimental/serverStatus` of rust-analyzer, quiescent once the workspace is loaded
+/// - `textDocument/clangd.fileStatus` of clangd, idle once a file is parsed
+///
+/// The server is ready when none of them reports pending work. It counts as busy until it first
+/// reports being idle or done with some work, or until the startup timeout for servers that
+/// never report their indexing. Once the timeouts are started, tokens that are created but never
+/// begun expire after the startup timeout, and the server counts as ready after being busy for
+/// the busy timeout in a row, in case it never reports the end of some work.
+#[derive(Clone)]
+pub struct ServerReadiness {
+    state: Arc<Mutex<ReadinessState>>,
+    ready: watch::Sender<bool>,
+}
+
+#[derive(Default)]
+struct ReadinessState {
+    /// Whether the server reported being idle or done with some work since it started.
+    reported: bool,
+    /// Titles of the running work done progresses, by token.
+    progress: HashMap<String, String>,
+    /// Tokens created by the server that no progress began with yet.
+    unstarted: HashSet<String>,
+    /// Files clangd is still working on.
+    busy_files: HashSet<String>,
+    quiescent: Option<bool>,
+    /// The startup and busy timeouts, once started.
+    timeouts: Option<(Duration, Duration)>,
+    /// Bumped each time the server becomes busy, a busy timeout only ends its own period.
+    busy_period: u64,
+}
+
+impl ReadinessState {
+    fn is_ready(&self) -> bool {
+        self.reported
+            && self.progress.is_empty()
+            && self.busy_files.is_empty()
+            && self.quiescent != Some(false)
+    }
+}
+
+impl ServerReadiness {
+    pub fn new() -> Self {
+        let (ready, _) = watch::channel(false);
+        Self {
+            state: Arc::new(Mutex::new(ReadinessState::default())),
+            ready,
+        }
+    }
+
+    /// Updates the state from a notification or request of the server.
+    pub fn handle_message(&self, message: &JsonRpcMessage) {
+        let (Some(method), Some(params)) = (message.method.as_deref(), message.params.as_ref())
+        else {
+            return;
+        };
+        let mut state = self.state.lock().unwrap();
+        match method {
+            "window/workDoneProgress/create" => {
+                if let Some(token) = params.get("token") {
+                    let token = token_key(token);
+                    state.progress.insert(token.clone(), String::new());
+                    state.unstarted.insert(token.clone());
+                    if let Some((startup, _)) = state.timeouts {
+                        self.expire_unstarted_after(token, startup);
+                    }
+                }
+            }
+            "$/progress" => {
+                let (Some(token), Some(value)) = (params.get("token"), params.get("value")) else {
+                    return;
+                };
+                state.unstarted.remove(&token_key(token));
+                match value.get("kind").and_then(Value::as_str) {
+                    Some("begin") | Some("report") => {
+                        let title = state.progress.entry(token_key(token)).or_default();
+                        if let Some(new_title) = value.get("title").and_then(Value::as_str) {
+                            *title = new_title.to_string();
+                        }
+                    }
+                    Some("end") => {
+                        state.progress.remove(&token_key(token));
+                        state.reported = true;
+                    }
+                    _ => {}
+                }
+            }
+            "experimental/serverStatus" => {
+                state.quiescent = params.get("quiescent").and_then(Value::as_bool);
+                state.reported |= state.quiescent == Some(true);
+            }
+            "textDocument/clangd.fileStatus" => {
+                let Some(uri) = params.get("uri").and_then(Value::as_str) else {
+                    return;
+                };
+                if params.get("state").and_then(Value::as_str) == Some("idle") {
+                    state.busy_files.remove(uri);
+                    state.reported = true;
+                } else {
+                    state.busy_files.insert(uri.to_string());
+                }
+            }
+            _ => return,
+        }
+        let ready = state.is_ready();
+        drop(state);
+        self.publish(ready);
+    }
+
+    /// Starts the timeouts once the server is initialized: the server counts as having reported
+    /// after `startup`, when tokens that were created but never begun expire too, and as ready
+    /// after being busy for `max_busy` in a row. A zero `max_busy` never ends the busy time.
+    pub fn start_timeouts(&self, startup: Duration, max_busy: Duration) {
+        let unstarted: Vec<String> = {
+            let mut state = self.state.lock().unwrap();
+            state.timeouts = Some((startup, max_busy));
+            state.unstarted.iter().cloned().collect()
+        };
+        for token in unstarted {
+            self.expire_unstarted_after(token, startup);
+        }
+        self.assume_reported_after(startup);
+        if !self.is_ready() {
+            self.expire_busy_period();
+        }
+    }
+
+    /// Counts the server as ready once `timeout` elapsed without it reporting anything, unless
+    /// it is busy with work it did report.
+    fn assume_reported_after(&self, timeout: Duration) {
+        let readiness = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let mut state = readiness.state.lock().unwrap();
+            if state.reported {
+                return;
+            }
+            debug!("Language server reported nothing after {:?}", timeout);
+            state.reported = true;
+            let ready = state.is_ready();
+            drop(state);
+            readiness.publish(ready);
+        });
+    }
+
+    /// Drops `token` if no progress began with it after `timeout`.
+    fn expire_unstarted_after(&self, token: String, timeout: Duration) {
+        let readiness = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let mut state = readiness.state.lock().unwrap();
+            if !state.unstarted.remove(&token) {
+                return;
+            }
+            debug!("Progress {} never began after {:?}", token, timeout);
+            state.progress.remove(&token);
+            let ready = state.is_ready();
+            drop(state);
+            readiness.publish(ready);
+        });
+    }
+
+    /// Counts the server as ready if it is still busy after the busy timeout, with nothing it
+    /// reported since in between.
+    fn expire_busy_period(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.busy_period += 1;
+        let period = state.busy_period;
+        let Some((_, timeout)) = state.timeouts.filter(|(_, timeout)| !timeout.is_zero()) else {
+            return;
+        };
+        drop(state);
+        let readiness = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            let mut state = readiness.state.lock().unwrap();
+            if state.busy_period != period || state.is_ready() {
+                return;
+            }
+            warn!(
+                "Language server still busy after {:?}, counting it as ready",
+                timeout
+            );
+            state.reported = true;
+            state.progress.clear();
+            state.unstarted.clear();
+            state.busy_files.clear();
+            state.quiescent = None;
+            drop(state);
+            readiness.publish(true);
+        });
+    }
+
+    fn publish(&self, ready: bool) {
+        let changed = self.ready.send_if_modified(|current| {
+            if *current == ready {
+                return false;
+            }
+            debug!("Language server {}", if ready { "ready" } else { "busy" });
+            *current = ready;
+            true
+        });
+        if changed && !ready {
+            self.expire_busy_period();
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        *self.ready.borrow()
+    }
+
+    /// Describes the work the server is busy with, e.g. `Indexing` or `parsing 2 files`.
+    pub fn pending_work(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        let mut work: Vec<String> = state
+            .progress
+            .values()
+            .filter(|title| !title.is_empty())
+            .cloned()
+            .collect();
+        work.sort();
+        if work.is_empty() && !state.progress.is_empty() {
+            work.push("work in progress".to_string());
+        }
+        if state.quiescent == Some(false) {
+            work.push("loading workspace".to_string());
+        }
+        if !state.busy_files.is_empty() {
+            let count = state.busy_files.len();
+            work.push(format!(
+                "parsing {} file{}",
+                count,
+                if count == 1 { "" } else { "s" }
+            ));
+        }
+        if work.is_empty() && !state.reported {
+            work.push("starting".to_string());
+        }
+        work
+    }
+
+    /// Waits until the server is ready, returns false if `timeout` elapsed first.
+    pub async fn wait(&self, timeout: Duration) -> bool {
+        let mut ready = self.ready.subscribe();
+        let result = tokio::time::timeout(timeout, ready.wait_for(|ready| *ready)).await;
+        result.is_ok_and(|changed| changed.is_ok())
+    }
+}
+
+impl Default for ServerReadiness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Progress tokens are either numbers or strings.
+fn token_key(token: &Value) -> String {
+    match token {
+        Value::String(token) => token.clone(),
+        token => token.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(method: &str, params: Value) -> JsonRpcMessage {
+        JsonRpcMessage {
+            jsonrpc: "2.0".to_string(),
+            id: None,
+            method: Some(method.to_string()),
+            params: Some(params),
+            result: None,
+            error: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_work_done_progress() {
+        let readiness = ServerReadiness::new();
+        readiness.handle_message(&message(
+            "window/workDoneProgress/create",
+            json!({"token": "index"}),
+        ));
+        assert!(!readiness.is_ready());
+        readiness.handle_message(&message(
+            "$/progress",
+            json!({"token": "index", "value": {"kind": "begin", "title": "Indexing"}}),
+        ));
+        assert_eq!(readiness.pending_work(), vec!["Indexing"]);
+        assert!(!readiness.wait(Duration::from_millis(10)).await);
+
+        readiness.handle_message(&message(
+            "$/progress",
+            json!({"token": "index", "value": {"kind": "end"}}),
+        ));
+        assert!(readiness.is_ready());
+        assert!(readiness.wait(Duration::from_millis(10)).await);
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_ready() {
+        let readiness = ServerReadiness::new();
+        readiness.handle_message(&message(
+            "experimental/serverStatus",
+            json!({"health": "ok", "quiescent": false}),
+        ));
+        assert_eq!(readiness.pending_work(), vec!["loading workspace"]);
+
+        let waiter = readiness.clone();
+        let handle = tokio::spawn(async move { waiter.wait(Duration::from_secs(5)).await });
+        readiness.handle_message(&message(
+            "experimental/serverStatus",
+            json!({"health": "ok", "quiescent": true}),
+        ));
+        assert!(handle.await.unwrap());
+    }
+
+    #[test]
+    fn test_clangd_file_status() {
+        let readiness = ServerReadiness::new();
+        readiness.handle_message(&message(
+            "textDocument/clangd.fileStatus",
+            json!({"uri": "file:///main.c", "state": "parsing includes"}),
+        ));
+        assert_eq!(readiness.pending_work(), vec!["parsing 1 file"]);
+        readiness.handle_message(&message(
+            "textDocument/clangd.fileStatus",
+            json!({"uri": "file:///main.c", "state": "idle"}),
+        ));
+        assert!(readiness.is_ready());
+    }
+
+    #[tokio::test]
+    async fn test_busy_until_reported() {
+        let readiness = ServerReadiness::new();
+        assert!(!readiness.is_ready());
+        assert_eq!(readiness.pending_work(), vec!["starting"]);
+
+        readiness.assume_reported_after(Duration::from_millis(10));
+        assert!(readiness.wait(Duration::from_secs(5)).await);
+        assert!(readiness.pending_work().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unstarted_progress_expires() {
+        let readiness = ServerReadiness::new();
+        readiness.handle_message(&message(
+            "$/progress",
+            json!({"token": "load", "value": {"kind": "end"}}),
+        ));
+        readiness.handle_message(&message(
+            "window/workDoneProgress/create",
+            json!({"token": "index"}),
+        ));
+        readiness.start_timeouts(Duration::from_millis(10), Duration::ZERO);
+        assert_eq!(readiness.pending_work(), vec!["work in progress"]);
+        assert!(readiness.wait(Duration::from_secs(5)).await);
+
+        // Begun progresses don't expire
+        readiness.handle_message(&message(
+            "window/workDoneProgress/create",
+            json!({"token": "check"}),
+        ));
+        readiness.handle_message(&message(
+            "$/progress",
+            json!({"token": "check", "value": {"kind": "begin", "title": "Checking"}}),
+        ));
+        assert!(!readiness.wait(Duration::from_millis(50)).await);
+        assert_eq!(readiness.pending_work(), vec!["Checking"]);
+    }
+
+    #[tokio::test]
+    async fn test_busy_timeout() {
+        let readiness = ServerReadiness::new();
+        readiness.start_timeouts(Duration::from_secs(5), Duration::from_millis(50));
+        readiness.handle_message(&message(
+            "$/progress",
+            json!({"token": 1, "value": {"kind": "begin", "title": "Indexing"}}),
+        ));
+        assert!(!readiness.wait(Duration::from_millis(10)).await);
+        assert!(readiness.wait(Duration::from_secs(5)).await);
+        assert!(readiness.pending_work().is_empty());
+
+        // Busy again, for another period
+        readiness.handle_message(&message(
+            "$/progress",
+            json!({"token": 2, "value": {"kind": "begin", "title": "Indexing"}}),
+        ));
+        assert!(!readiness.is_ready());
+        assert!(readiness.wait(Duration::from_secs(5)).await);
+    }
+
+    #[tokio::test]
+    async fn test_startup_timeout_keeps_reported_work() {
+        let readiness = ServerReadiness::new();
+        readiness.handle_message(&message(
+            "$/progress",
+            json!({"token": 1, "value": {"kind": "begin", "title": "Indexing"}}),
+        ));
+        readiness.assume_reported_after(Duration::from_millis(10));
+        assert!(!readiness.wait(Duration::from_millis(50)).await);
+        assert_eq!(readiness.pending_work(), vec!["Indexing"]);
+    }
+}
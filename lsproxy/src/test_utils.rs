@@ -33,6 +33,34 @@ pub fn c_sample_path() -> String {
     "/mnt/lsproxy_root/sample_project/c".to_string()
 }
 
+pub fn bash_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/bash".to_string()
+}
+
+pub fn perl_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/perl".to_string()
+}
+
+pub fn csharp_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/csharp".to_string()
+}
+
+pub fn ruby_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/ruby".to_string()
+}
+
+pub fn php_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/php".to_string()
+}
+
+pub fn frontend_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/frontend".to_string()
+}
+
+pub fn jupyter_sample_path() -> String {
+    "/mnt/lsproxy_root/sample_project/jupyter".to_string()
+}
+
 pub struct TestContext {
     pub manager: Option<Manager>,
 }
@@ -0,0 +1,538 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+
+use log::warn;
+use regex::Regex;
+
+use super::file_utils::search_files;
+use super::workspace_documents::{
+    BASH_EXTENSIONS, BASH_FILE_PATTERNS, CPP_EXTENSIONS, CSHARP_EXTENSIONS, CSHARP_FILE_PATTERNS,
+    C_AND_CPP_FILE_PATTERNS, C_EXTENSIONS, DEFAULT_EXCLUDE_PATTERNS, JAVASCRIPT_EXTENSIONS,
+    JAVA_EXTENSIONS, JAVA_FILE_PATTERNS, PERL_EXTENSIONS, PERL_FILE_PATTERNS, PHP_EXTENSIONS,
+    PHP_FILE_PATTERNS, PYTHON_EXTENSIONS, PYTHON_FILE_PATTERNS, RUBY_EXTENSIONS,
+    RUBY_FILE_PATTERNS, RUST_EXTENSIONS, RUST_FILE_PATTERNS, SVELTE_EXTENSIONS,
+    SVELTE_FILE_PATTERNS, TYPESCRIPT_AND_JAVASCRIPT_FILE_PATTERNS, TYPESCRIPT_EXTENSIONS,
+    VUE_EXTENSIONS,
+};
+use crate::api_types::{get_mount_dir, SupportedLanguages};
+
+/// A `languageId` with the language server handling it, the ast-grep language its symbols
+/// are extracted with and the extension given to copies of extensionless files.
+pub struct LanguageId {
+    pub id: &'static str,
+    pub server: SupportedLanguages,
+    pub ast_grep_language: Option<&'static str>,
+    pub extension: &'static str,
+}
+
+pub const LANGUAGE_IDS: &[LanguageId] = &[
+    LanguageId {
+        id: "python",
+        server: SupportedLanguages::Python,
+        ast_grep_language: Some("python"),
+        extension: "py",
+    },
+    LanguageId {
+        id: "typescript",
+        server: SupportedLanguages::TypeScriptJavaScript,
+        ast_grep_language: Some("tsx"),
+        extension: "ts",
+    },
+    LanguageId {
+        id: "javascript",
+        server: SupportedLanguages::TypeScriptJavaScript,
+        ast_grep_language: Some("javascript"),
+        extension: "js",
+    },
+    LanguageId {
+        id: "vue",
+        server: SupportedLanguages::TypeScriptJavaScript,
+        ast_grep_language: Some("html"),
+        extension: "vue",
+    },
+    LanguageId {
+        id: "rust",
+        server: SupportedLanguages::Rust,
+        ast_grep_language: Some("rust"),
+        extension: "rs",
+    },
+    LanguageId {
+        id: "c",
+        server: SupportedLanguages::CPP,
+        ast_grep_language: Some("cpp"),
+        extension: "c",
+    },
+    LanguageId {
+        id: "cpp",
+        server: SupportedLanguages::CPP,
+        ast_grep_language: Some("cpp"),
+        extension: "cpp",
+    },
+    LanguageId {
+        id: "java",
+        server: SupportedLanguages::Java,
+        ast_grep_language: Some("java"),
+        extension: "java",
+    },
+    LanguageId {
+        id: "shellscript",
+        server: SupportedLanguages::Bash,
+        ast_grep_language: Some("bash"),
+        extension: "sh",
+    },
+    LanguageId {
+        id: "perl",
+        server: SupportedLanguages::Perl,
+        ast_grep_language: Some("perl"),
+        extension: "pl",
+    },
+    LanguageId {
+        id: "csharp",
+        server: SupportedLanguages::CSharp,
+        ast_grep_language: Some("csharp"),
+        extension: "cs",
+    },
+    LanguageId {
+        id: "ruby",
+        server: SupportedLanguages::Ruby,
+        ast_grep_language: Some("ruby"),
+        extension: "rb",
+    },
+    LanguageId {
+        id: "php",
+        server: SupportedLanguages::PHP,
+        ast_grep_language: Some("php"),
+        extension: "php",
+    },
+    LanguageId {
+        id: "svelte",
+        server: SupportedLanguages::Svelte,
+        ast_grep_language: Some("html"),
+        extension: "svelte",
+    },
+];
+
+/// Only the start of a file is read to sniff its language.
+const SNIFF_BYTES: u64 = 64 * 1024;
+
+/// Extensions mapped to a `languageId` by the `[extensions]` section of the configuration.
+static EXTENSION_OVERRIDES: LazyLock<RwLock<HashMap<String, String>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+static VIM_MODELINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|\s)(?:vi|vim|ex)(?:[<=>]?\d+)?:\s*(?:set?\s+)?(.*)").unwrap()
+});
+
+pub fn set_extension_overrides(overrides: HashMap<String, String>) {
+    *EXTENSION_OVERRIDES.write().unwrap() = overrides;
+}
+
+pub fn language(id: &str) -> Option<&'static LanguageId> {
+    LANGUAGE_IDS.iter().find(|language| language.id == id)
+}
+
+pub fn supported_language(id: &str) -> Option<SupportedLanguages> {
+    language(id).map(|language| language.server)
+}
+
+/// Detects the `languageId` of `file_path`, relative paths are resolved against the mount dir.
+///
+/// Configured extensions take precedence over the built-in ones. Files without a known
+/// extension are recognized by their shebang or their vim or emacs modeline.
+pub fn detect_language_id(file_path: &str) -> Option<String> {
+    let path = get_mount_dir().join(file_path);
+    detect_language_id_with(&path, &EXTENSION_OVERRIDES.read().unwrap())
+}
+
+fn detect_language_id_with(path: &Path, overrides: &HashMap<String, String>) -> Option<String> {
+    if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+        if let Some(id) = overrides.get(extension) {
+            return Some(id.clone());
+        }
+        // `.h` is shared by C and C++, tell them apart by content.
+        if extension == "h" {
+            let content = read_head(path).unwrap_or_default();
+            return Some(header_language_id(&content).to_string());
+        }
+        if let Some(id) = default_language_id(extension) {
+            return Some(id.to_string());
+        }
+    }
+    read_head(path)
+        .and_then(|content| sniff_language_id(&content))
+        .map(str::to_string)
+}
+
+/// Returns the extension to give a copy of `path` so tools that only look at file names,
+/// like ast-grep, recognize the language sniffed from its content.
+///
+/// None if the language of the file is already known from its extension.
+pub fn sniffed_extension(path: &Path) -> Option<&'static str> {
+    let overrides = EXTENSION_OVERRIDES.read().unwrap();
+    if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+        if overrides.contains_key(extension) || default_language_id(extension).is_some() {
+            return None;
+        }
+    }
+    let content = read_head(path)?;
+    language(sniff_language_id(&content)?).map(|language| language.extension)
+}
+
+fn default_language_id(extension: &str) -> Option<&'static str> {
+    match extension {
+        ext if PYTHON_EXTENSIONS.contains(&ext) => Some("python"),
+        ext if TYPESCRIPT_EXTENSIONS.contains(&ext) => Some("typescript"),
+        ext if JAVASCRIPT_EXTENSIONS.contains(&ext) => Some("javascript"),
+        ext if VUE_EXTENSIONS.contains(&ext) => Some("vue"),
+        ext if RUST_EXTENSIONS.contains(&ext) => Some("rust"),
+        ext if C_EXTENSIONS.contains(&ext) => Some("c"),
+        ext if CPP_EXTENSIONS.contains(&ext) => Some("cpp"),
+        ext if JAVA_EXTENSIONS.contains(&ext) => Some("java"),
+        ext if BASH_EXTENSIONS.contains(&ext) => Some("shellscript"),
+        ext if PERL_EXTENSIONS.contains(&ext) => Some("perl"),
+        ext if CSHARP_EXTENSIONS.contains(&ext) => Some("csharp"),
+        ext if RUBY_EXTENSIONS.contains(&ext) => Some("ruby"),
+        ext if PHP_EXTENSIONS.contains(&ext) => Some("php"),
+        ext if SVELTE_EXTENSIONS.contains(&ext) => Some("svelte"),
+        _ => None,
+    }
+}
+
+/// Headers are C unless they use a construct that only exists in C++.
+fn header_language_id(content: &str) -> &'static str {
+    static CPP_CONSTRUCTS: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(
+            r"(?m)^\s*(?:class\s+\w+\s*[:{]|namespace\b|template\s*<|using\s+namespace\b|(?:public|private|protected)\s*:)|\bstd::",
+        )
+        .unwrap()
+    });
+    if CPP_CONSTRUCTS.is_match(content) {
+        "cpp"
+    } else {
+        "c"
+    }
+}
+
+fn read_head(path: &Path) -> Option<String> {
+    let mut bytes = Vec::new();
+    std::fs::File::open(path)
+        .ok()?
+        .take(SNIFF_BYTES)
+        .read_to_end(&mut bytes)
+        .ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Detects a language from the shebang or the modeline of a script.
+pub fn sniff_language_id(content: &str) -> Option<&'static str> {
+    shebang_language_id(content)
+        .or_else(|| emacs_modeline_language_id(content))
+        .or_else(|| vim_modeline_language_id(content))
+}
+
+/// e.g. `#!/usr/bin/python3`, `#!/usr/bin/env -S node --no-warnings`
+fn shebang_language_id(content: &str) -> Option<&'static str> {
+    let line = content.lines().next()?.strip_prefix("#!")?;
+    let mut words = line.split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        interpreter = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+    }
+    alias_language_id(interpreter)
+}
+
+/// e.g. `# -*- mode: python -*-` or `// -*- c++ -*-`, on the first line or after a shebang.
+fn emacs_modeline_language_id(content: &str) -> Option<&'static str> {
+    content.lines().take(2).find_map(|line| {
+        let (_, rest) = line.split_once("-*-")?;
+        let (variables, _) = rest.split_once("-*-")?;
+        if !variables.contains(':') {
+            return alias_language_id(variables.trim());
+        }
+        variables.split(';').find_map(|variable| {
+            let (name, value) = variable.split_once(':')?;
+            if name.trim().eq_ignore_ascii_case("mode") {
+                alias_language_id(value.trim())
+            } else {
+                None
+            }
+        })
+    })
+}
+
+/// e.g. `# vim: set ft=python:` or `# vi: filetype=sh`, in the first or last 5 lines.
+fn vim_modeline_language_id(content: &str) -> Option<&'static str> {
+    let lines: Vec<&str> = content.lines().collect();
+    let head = &lines[..lines.len().min(5)];
+    let tail = &lines[lines.len().saturating_sub(5)..];
+    head.iter().chain(tail).find_map(|line| {
+        let options = VIM_MODELINE.captures(line)?.get(1)?.as_str();
+        options
+            .split(|c: char| c == ':' || c.is_whitespace())
+            .find_map(|option| {
+                let (name, value) = option.split_once('=')?;
+                match name {
+                    "ft" | "filetype" | "syntax" | "syn" => alias_language_id(value),
+                    _ => None,
+                }
+            })
+    })
+}
+
+/// Maps interpreters, vim filetypes and emacs modes to a `languageId`, ignoring versions
+/// like in `python3.12`.
+fn alias_language_id(name: &str) -> Option<&'static str> {
+    let name = name.to_ascii_lowercase();
+    let name = name.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    match name {
+        "python" | "pythonw" | "pypy" => Some("python"),
+        "sh" | "bash" | "zsh" | "dash" | "ksh" | "mksh" | "ash" | "shell-script"
+        | "shellscript" => Some("shellscript"),
+        "perl" | "cperl" => Some("perl"),
+        "ruby" | "jruby" => Some("ruby"),
+        "node" | "nodejs" | "bun" | "javascript" | "js" => Some("javascript"),
+        "ts-node" | "tsx" | "typescript" | "ts" => Some("typescript"),
+        "php" => Some("php"),
+        "c" => Some("c"),
+        "cpp" | "c++" => Some("cpp"),
+        "rust" => Some("rust"),
+        "java" => Some("java"),
+        "cs" | "csharp" => Some("csharp"),
+        _ => None,
+    }
+}
+
+/// Built-in file patterns of the language server of `lsp`.
+fn default_file_patterns(lsp: SupportedLanguages) -> &'static [&'static str] {
+    match lsp {
+        SupportedLanguages::Python => PYTHON_FILE_PATTERNS,
+        SupportedLanguages::TypeScriptJavaScript => TYPESCRIPT_AND_JAVASCRIPT_FILE_PATTERNS,
+        SupportedLanguages::Rust => RUST_FILE_PATTERNS,
+        SupportedLanguages::CPP => C_AND_CPP_FILE_PATTERNS,
+        SupportedLanguages::Java => JAVA_FILE_PATTERNS,
+        SupportedLanguages::Bash => BASH_FILE_PATTERNS,
+        SupportedLanguages::Perl => PERL_FILE_PATTERNS,
+        SupportedLanguages::CSharp => CSHARP_FILE_PATTERNS,
+        SupportedLanguages::Ruby => RUBY_FILE_PATTERNS,
+        SupportedLanguages::PHP => PHP_FILE_PATTERNS,
+        SupportedLanguages::Svelte => SVELTE_FILE_PATTERNS,
+    }
+}
+
+/// File patterns of the language server of `lsp` with the configured extensions applied.
+pub fn file_patterns(lsp: SupportedLanguages) -> Vec<String> {
+    file_patterns_with(lsp, &EXTENSION_OVERRIDES.read().unwrap())
+}
+
+/// Extensions mapped to another server are dropped, extensions mapped to `lsp` are added.
+fn file_patterns_with(lsp: SupportedLanguages, overrides: &HashMap<String, String>) -> Vec<String> {
+    let mut patterns: Vec<String> = default_file_patterns(lsp)
+        .iter()
+        .filter(|pattern| {
+            let extension = pattern.rsplit_once("*.").map(|(_, ext)| ext);
+            !extension
+                .and_then(|ext| overrides.get(ext))
+                .is_some_and(|id| supported_language(id) != Some(lsp))
+        })
+        .map(|pattern| pattern.to_string())
+        .collect();
+    let mut extensions: Vec<&String> = overrides
+        .iter()
+        .filter(|(_, id)| supported_language(id) == Some(lsp))
+        .map(|(ext, _)| ext)
+        .collect();
+    extensions.sort();
+    for extension in extensions {
+        let pattern = format!("**/*.{}", extension);
+        if !patterns.contains(&pattern) {
+            patterns.push(pattern);
+        }
+    }
+    patterns
+}
+
+/// `languageGlobs` for ast-grep that map the configured extensions to their ast-grep language.
+pub fn ast_grep_language_globs(
+    overrides: &HashMap<String, String>,
+) -> BTreeMap<String, Vec<String>> {
+    let mut globs: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (extension, id) in overrides {
+        if let Some(ast_grep_language) = language(id).and_then(|l| l.ast_grep_language) {
+            globs
+                .entry(ast_grep_language.to_string())
+                .or_default()
+                .push(format!("*.{}", extension));
+        }
+    }
+    for patterns in globs.values_mut() {
+        patterns.sort();
+    }
+    globs
+}
+
+/// Finds the files without an extension under `root_path` whose shebang or modeline names a
+/// supported language, grouped by language server.
+pub fn find_scripts(root_path: &Path) -> HashMap<SupportedLanguages, Vec<PathBuf>> {
+    let mut scripts: HashMap<SupportedLanguages, Vec<PathBuf>> = HashMap::new();
+    let files = search_files(
+        root_path,
+        vec!["**/*".to_string()],
+        DEFAULT_EXCLUDE_PATTERNS
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+        true,
+    )
+    .unwrap_or_else(|e| {
+        warn!("Error searching scripts: {}", e);
+        Vec::new()
+    });
+    for file in files.into_iter().filter(|f| f.extension().is_none()) {
+        let Some(content) = read_head(&file) else {
+            continue;
+        };
+        if let Some(lsp) = sniff_language_id(&content).and_then(supported_language) {
+            scripts.entry(lsp).or_default().push(file);
+        }
+    }
+    scripts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_shebangs() {
+        assert_eq!(
+            sniff_language_id("#!/usr/bin/env python3\n"),
+            Some("python")
+        );
+        assert_eq!(
+            sniff_language_id("#!/usr/bin/python3.12 -u\n"),
+            Some("python")
+        );
+        assert_eq!(
+            sniff_language_id("#!/bin/bash\nset -e\n"),
+            Some("shellscript")
+        );
+        assert_eq!(
+            sniff_language_id("#!/usr/bin/env -S node --no-warnings\n"),
+            Some("javascript")
+        );
+        assert_eq!(
+            sniff_language_id("#!/usr/bin/env ts-node\n"),
+            Some("typescript")
+        );
+        assert_eq!(sniff_language_id("#!/usr/bin/perl -w\n"), Some("perl"));
+        assert_eq!(sniff_language_id("#!/usr/bin/awk -f\n"), None);
+        assert_eq!(sniff_language_id("print('no shebang')\n"), None);
+    }
+
+    #[test]
+    fn test_modelines() {
+        assert_eq!(
+            sniff_language_id("# -*- mode: ruby; coding: utf-8 -*-\nputs 1\n"),
+            Some("ruby")
+        );
+        assert_eq!(sniff_language_id("// -*- C++ -*-\n"), Some("cpp"));
+        assert_eq!(
+            sniff_language_id("echo hi\n\n# vim: set ts=4 ft=sh:\n"),
+            Some("shellscript")
+        );
+        assert_eq!(sniff_language_id("# vi: filetype=python\n"), Some("python"));
+        assert_eq!(sniff_language_id("the environment: ft=python\n"), None);
+    }
+
+    #[test]
+    fn test_detect_language_id() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let script = dir.path().join("manage");
+        fs::write(&script, "#!/usr/bin/env python3\nprint('hi')\n")?;
+        let c_header = dir.path().join("list.h");
+        fs::write(&c_header, "struct list { int value; };\n")?;
+        let cpp_header = dir.path().join("graph.h");
+        fs::write(&cpp_header, "namespace graph {\nclass Node {};\n}\n")?;
+
+        let no_overrides = HashMap::new();
+        assert_eq!(
+            detect_language_id_with(&script, &no_overrides).as_deref(),
+            Some("python")
+        );
+        assert_eq!(
+            detect_language_id_with(&c_header, &no_overrides).as_deref(),
+            Some("c")
+        );
+        assert_eq!(
+            detect_language_id_with(&cpp_header, &no_overrides).as_deref(),
+            Some("cpp")
+        );
+        assert_eq!(
+            detect_language_id_with(Path::new("/workspace/main.rs"), &no_overrides).as_deref(),
+            Some("rust")
+        );
+        assert_eq!(
+            detect_language_id_with(Path::new("/workspace/README.md"), &no_overrides),
+            None
+        );
+
+        let overrides = HashMap::from([
+            ("h".to_string(), "cpp".to_string()),
+            ("inc".to_string(), "php".to_string()),
+        ]);
+        assert_eq!(
+            detect_language_id_with(&c_header, &overrides).as_deref(),
+            Some("cpp")
+        );
+        assert_eq!(
+            detect_language_id_with(Path::new("/workspace/header.inc"), &overrides).as_deref(),
+            Some("php")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_patterns_with_overrides() {
+        let overrides = HashMap::from([
+            ("inc".to_string(), "php".to_string()),
+            ("pyx".to_string(), "c".to_string()),
+        ]);
+        assert_eq!(
+            file_patterns_with(SupportedLanguages::PHP, &overrides),
+            vec!["**/*.php", "**/*.inc"]
+        );
+        assert_eq!(
+            file_patterns_with(SupportedLanguages::Python, &overrides),
+            vec!["**/*.py", "**/*.pyi", "**/*.ipynb"]
+        );
+        assert!(file_patterns_with(SupportedLanguages::CPP, &overrides)
+            .contains(&"**/*.pyx".to_string()));
+        assert_eq!(
+            ast_grep_language_globs(&overrides),
+            BTreeMap::from([
+                ("cpp".to_string(), vec!["*.pyx".to_string()]),
+                ("php".to_string(), vec!["*.inc".to_string()]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_find_scripts() -> Result<(), Box<dyn std::error::Error>> {
+        // The default excludes skip hidden directories like `/tmp/.tmpXXXX`.
+        let dir = tempfile::Builder::new().prefix("scripts").tempdir()?;
+        fs::write(dir.path().join("deploy"), "#!/bin/sh\necho deploy\n")?;
+        fs::write(dir.path().join("LICENSE"), "MIT License\n")?;
+        fs::write(dir.path().join("main.py"), "#!/usr/bin/env python3\n")?;
+
+        let scripts = find_scripts(dir.path());
+        assert_eq!(
+            scripts,
+            HashMap::from([(SupportedLanguages::Bash, vec![dir.path().join("deploy")])])
+        );
+        Ok(())
+    }
+}
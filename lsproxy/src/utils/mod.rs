@@ -1,2 +1,4 @@
 pub(crate) mod file_utils;
+pub(crate) mod language_detection;
+pub(crate) mod notebook;
 pub(crate) mod workspace_documents;
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+
+use log::warn;
+use serde::Deserialize;
+
+use crate::api_types::{get_mount_dir, FilePosition, FileRange};
+
+pub const NOTEBOOK_EXTENSION: &str = "ipynb";
+
+pub fn is_notebook(file_path: impl AsRef<Path>) -> bool {
+    file_path
+        .as_ref()
+        .extension()
+        .is_some_and(|ext| ext == NOTEBOOK_EXTENSION)
+}
+
+#[derive(Deserialize)]
+struct RawNotebook {
+    cells: Vec<RawCell>,
+}
+
+#[derive(Deserialize)]
+struct RawCell {
+    cell_type: String,
+    source: RawSource,
+}
+
+/// nbformat allows the cell source as one string or as a list of lines.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawSource {
+    Text(String),
+    Lines(Vec<String>),
+}
+
+#[derive(Debug, PartialEq)]
+struct CodeCell {
+    index: u32,
+    start_line: u32,
+    line_count: u32,
+}
+
+/// A Jupyter notebook seen as a single Python document.
+///
+/// The code cells are concatenated in order, separated by an empty line.
+/// IPython magics and shell escapes (`%time`, `!pip install`) are commented out,
+/// which keeps every character at the same column.
+#[derive(Debug)]
+pub struct NotebookDocument {
+    pub text: String,
+    cells: Vec<CodeCell>,
+}
+
+impl NotebookDocument {
+    pub fn parse(content: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let notebook: RawNotebook = serde_json::from_str(content)
+            .map_err(|e| format!("Failed to parse notebook: {}", e))?;
+
+        let mut lines: Vec<String> = Vec::new();
+        let mut cells = Vec::new();
+        for (index, cell) in notebook.cells.into_iter().enumerate() {
+            if cell.cell_type != "code" {
+                continue;
+            }
+            let source = match cell.source {
+                RawSource::Text(text) => text,
+                RawSource::Lines(source_lines) => source_lines.concat(),
+            };
+            let start_line = lines.len() as u32;
+            lines.extend(source.lines().map(comment_out_magic));
+            cells.push(CodeCell {
+                index: index as u32,
+                start_line,
+                line_count: lines.len() as u32 - start_line,
+            });
+            lines.push(String::new());
+        }
+
+        Ok(Self {
+            text: lines.join("\n"),
+            cells,
+        })
+    }
+
+    /// Maps a line of the document to the index of its cell and the line within the cell.
+    pub fn to_cell_line(&self, line: u32) -> Option<(u32, u32)> {
+        self.cells
+            .iter()
+            .rev()
+            .find(|cell| cell.start_line <= line)
+            .map(|cell| (cell.index, line - cell.start_line))
+    }
+
+    /// Maps a line within the cell at `index` to the line of the document.
+    pub fn to_document_line(&self, index: u32, line: u32) -> Option<u32> {
+        self.cells
+            .iter()
+            .find(|cell| cell.index == index && line < cell.line_count.max(1))
+            .map(|cell| cell.start_line + line)
+    }
+}
+
+fn comment_out_magic(line: &str) -> String {
+    let indent = line.len() - line.trim_start().len();
+    match line[indent..].chars().next() {
+        Some('%') | Some('!') => format!("{}#{}", &line[..indent], &line[indent + 1..]),
+        _ => line.to_string(),
+    }
+}
+
+/// Translates positions between notebook documents and their cells.
+///
+/// Language servers only know the concatenated document, the API speaks in cells.
+/// Each notebook is read once per instance.
+#[derive(Default)]
+pub struct NotebookPositions {
+    documents: HashMap<String, Option<NotebookDocument>>,
+}
+
+impl NotebookPositions {
+    fn document(&mut self, path: &str) -> Option<&NotebookDocument> {
+        if !is_notebook(path) {
+            return None;
+        }
+        self.documents
+            .entry(path.to_string())
+            .or_insert_with(|| {
+                std::fs::read_to_string(get_mount_dir().join(path))
+                    .map_err(|e| e.into())
+                    .and_then(|content| NotebookDocument::parse(&content))
+                    .map_err(|e| warn!("Failed to load notebook {}: {}", path, e))
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    /// Returns the document line of a position from a request.
+    pub fn document_line(
+        &mut self,
+        path: &str,
+        cell: Option<u32>,
+        line: u32,
+    ) -> Result<u32, String> {
+        let Some(cell) = cell else {
+            return Ok(line);
+        };
+        self.document(path)
+            .ok_or_else(|| format!("{} is not a notebook", path))?
+            .to_document_line(cell, line)
+            .ok_or_else(|| format!("Line {} of cell {} not found in {}", line, cell, path))
+    }
+
+    /// Makes a document position from a language server relative to its cell.
+    pub fn map_position(&mut self, position: &mut FilePosition) {
+        if position.cell.is_some() {
+            return;
+        }
+        if let Some((cell, line)) = self
+            .document(&position.path)
+            .and_then(|document| document.to_cell_line(position.position.line))
+        {
+            position.cell = Some(cell);
+            position.position.line = line;
+        }
+    }
+
+    /// Makes a document range from a language server relative to the cell it starts in.
+    pub fn map_range(&mut self, range: &mut FileRange) {
+        if range.cell.is_some() {
+            return;
+        }
+        if let Some((cell, line)) = self
+            .document(&range.path)
+            .and_then(|document| document.to_cell_line(range.start.line))
+        {
+            range.cell = Some(cell);
+            range.end.line -= range.start.line - line;
+            range.start.line = line;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{
+        "cells": [
+            {"cell_type": "markdown", "metadata": {}, "source": ["# Title"]},
+            {"cell_type": "code", "metadata": {}, "outputs": [], "execution_count": 1,
+             "source": ["%matplotlib inline\n", "import math\n", "def area(r):\n", "    return math.pi * r ** 2"]},
+            {"cell_type": "code", "metadata": {}, "outputs": [], "execution_count": 2,
+             "source": "print(area(2))"}
+        ],
+        "metadata": {}, "nbformat": 4, "nbformat_minor": 5
+    }"##;
+
+    #[test]
+    fn test_parse_notebook() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let document = NotebookDocument::parse(NOTEBOOK)?;
+        assert_eq!(
+            document.text,
+            "#matplotlib inline\nimport math\ndef area(r):\n    return math.pi * r ** 2\n\nprint(area(2))\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_lines() -> Result<(), Box<dyn Error + Send + Sync>> {
+        let document = NotebookDocument::parse(NOTEBOOK)?;
+        assert_eq!(document.to_cell_line(2), Some((1, 2)));
+        assert_eq!(document.to_cell_line(5), Some((2, 0)));
+        assert_eq!(document.to_document_line(1, 2), Some(2));
+        assert_eq!(document.to_document_line(2, 0), Some(5));
+        assert_eq!(document.to_document_line(0, 0), None);
+        assert_eq!(document.to_document_line(2, 1), None);
+        Ok(())
+    }
+}
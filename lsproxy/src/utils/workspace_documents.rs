@@ -1,7 +1,9 @@
 use crate::utils::file_utils::search_files;
+use crate::utils::notebook::{is_notebook, NotebookDocument};
 use log::{debug, error, warn};
 use lsp_types::Range;
 use notify_debouncer_mini::DebouncedEvent;
+use serde::Deserialize;
 use std::{
     collections::{HashMap, HashSet},
     error::Error,
@@ -33,17 +35,22 @@ pub const PYTHON_ROOT_FILES: &[&str] = &[
     "pyrightconfig.json",
 ];
 
-pub const PYTHON_FILE_PATTERNS: &[&str] = &["**/*.py", "**/*.pyx", "**/*.pyi"];
-pub const PYTHON_EXTENSIONS: &[&str] = &["py", "pyx", "pyi"];
+pub const PYTHON_FILE_PATTERNS: &[&str] = &["**/*.py", "**/*.pyx", "**/*.pyi", "**/*.ipynb"];
+pub const PYTHON_EXTENSIONS: &[&str] = &["py", "pyx", "pyi", "ipynb"];
 
 pub const TYPESCRIPT_AND_JAVASCRIPT_ROOT_FILES: &[&str] =
     &["tsconfig.json", "jsconfig.json", "package.json"];
 
+/// Vue single-file components are served by tsserver with the Vue plugin.
 pub const TYPESCRIPT_AND_JAVASCRIPT_FILE_PATTERNS: &[&str] =
-    &["**/*.ts", "**/*.tsx", "**/*.js", "**/*.jsx"];
+    &["**/*.ts", "**/*.tsx", "**/*.js", "**/*.jsx", "**/*.vue"];
 pub const TYPESCRIPT_EXTENSIONS: &[&str] = &["ts", "tsx"];
 pub const JAVASCRIPT_EXTENSIONS: &[&str] = &["js", "jsx"];
-pub const TYPESCRIPT_AND_JAVASCRIPT_EXTENSIONS: &[&str] = &["ts", "tsx", "js", "jsx"];
+pub const VUE_EXTENSIONS: &[&str] = &["vue"];
+
+pub const SVELTE_ROOT_FILES: &[&str] = &["svelte.config.js", "svelte.config.mjs", "package.json"];
+pub const SVELTE_FILE_PATTERNS: &[&str] = &["**/*.svelte"];
+pub const SVELTE_EXTENSIONS: &[&str] = &["svelte"];
 
 pub const RUST_ROOT_FILES: &[&str] = &["Cargo.toml"];
 pub const RUST_FILE_PATTERNS: &[&str] = &["**/*.rs"];
@@ -63,15 +70,36 @@ pub const C_AND_CPP_FILE_PATTERNS: &[&str] = &[
     "**/*.cpp", "**/*.cc", "**/*.c", "**/*.cxx", "**/*.h", "**/*.hpp", "**/*.hxx", "**/*.hh",
 ];
 
+/// `.h` headers are C or C++ depending on their content, see `language_detection`.
 pub const C_EXTENSIONS: &[&str] = &["c", "h"];
-pub const CPP_EXTENSIONS: &[&str] = &["cpp", "cc", "cxx", "h", "hpp", "hxx", "hh"];
-pub const C_AND_CPP_EXTENSIONS: &[&str] = &["cpp", "cc", "c", "cxx", "h", "hpp", "hxx", "hh"];
+pub const CPP_EXTENSIONS: &[&str] = &["cpp", "cc", "cxx", "hpp", "hxx", "hh"];
 
 pub const JAVA_ROOT_FILES: &[&str] = &["gradlew", ".git", "mvnw"];
 pub const JAVA_FILE_PATTERNS: &[&str] = &["**/*.java"];
 pub const JAVA_EXTENSIONS: &[&str] = &["java"];
 
-#[derive(Clone, PartialEq)]
+pub const BASH_ROOT_FILES: &[&str] = &[".git"];
+pub const BASH_FILE_PATTERNS: &[&str] = &["**/*.sh", "**/*.bash"];
+pub const BASH_EXTENSIONS: &[&str] = &["sh", "bash"];
+
+pub const PERL_ROOT_FILES: &[&str] = &["Makefile.PL", "Build.PL", "cpanfile", "dist.ini"];
+pub const PERL_FILE_PATTERNS: &[&str] = &["**/*.pl", "**/*.pm"];
+pub const PERL_EXTENSIONS: &[&str] = &["pl", "pm"];
+
+pub const CSHARP_ROOT_FILES: &[&str] = &["*.sln", "*.csproj"];
+pub const CSHARP_FILE_PATTERNS: &[&str] = &["**/*.cs"];
+pub const CSHARP_EXTENSIONS: &[&str] = &["cs"];
+
+pub const RUBY_ROOT_FILES: &[&str] = &["Gemfile", ".ruby-version"];
+pub const RUBY_FILE_PATTERNS: &[&str] = &["**/*.rb", "**/*.rake"];
+pub const RUBY_EXTENSIONS: &[&str] = &["rb", "rake"];
+
+pub const PHP_ROOT_FILES: &[&str] = &["composer.json"];
+pub const PHP_FILE_PATTERNS: &[&str] = &["**/*.php"];
+pub const PHP_EXTENSIONS: &[&str] = &["php"];
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum DidOpenConfiguration {
     Lazy,
     None,
@@ -164,7 +192,10 @@ impl WorkspaceDocumentsHandler {
                     warn!("File {:?} contains invalid UTF-8", full_file_path);
                 }
 
-                let content = String::from_utf8_lossy(&bytes).into_owned();
+                let mut content = String::from_utf8_lossy(&bytes).into_owned();
+                if is_notebook(full_file_path) {
+                    content = NotebookDocument::parse(&content)?.text;
+                }
                 cache.insert(full_file_path.clone(), Some(content.clone()));
                 Ok(content)
             }
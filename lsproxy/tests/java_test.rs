@@ -100,12 +100,17 @@ fn test_server_integration_java() -> Result<(), Box<dyn std::error::Error>> {
         Symbol {
             name: String::from("AStar"),
             kind: String::from("class"),
+            container_name: None,
+            qualified_name: String::from("AStar"),
+            decorators: Vec::new(),
+            documentation: None,
             identifier_position: FilePosition {
                 path: String::from("AStar.java"),
                 position: Position {
                     line: 10,
                     character: 13,
                 },
+                cell: None,
             },
             range: FileRange {
                 path: String::from("AStar.java"),
@@ -117,17 +122,303 @@ fn test_server_integration_java() -> Result<(), Box<dyn std::error::Error>> {
                     line: 96,
                     character: 21,
                 },
+                cell: None,
+            },
+        },
+        Symbol {
+            name: String::from("open"),
+            kind: String::from("field"),
+            container_name: Some(String::from("AStar")),
+            qualified_name: String::from("AStar.open"),
+            decorators: Vec::new(),
+            documentation: None,
+            identifier_position: FilePosition {
+                path: String::from("AStar.java"),
+                position: Position {
+                    line: 11,
+                    character: 29,
+                },
+                cell: None,
+            },
+            range: FileRange {
+                path: String::from("AStar.java"),
+                start: Position {
+                    line: 11,
+                    character: 0,
+                },
+                end: Position {
+                    line: 11,
+                    character: 34,
+                },
+                cell: None,
+            },
+        },
+        Symbol {
+            name: String::from("closed"),
+            kind: String::from("field"),
+            container_name: Some(String::from("AStar")),
+            qualified_name: String::from("AStar.closed"),
+            decorators: Vec::new(),
+            documentation: None,
+            identifier_position: FilePosition {
+                path: String::from("AStar.java"),
+                position: Position {
+                    line: 12,
+                    character: 29,
+                },
+                cell: None,
+            },
+            range: FileRange {
+                path: String::from("AStar.java"),
+                start: Position {
+                    line: 12,
+                    character: 0,
+                },
+                end: Position {
+                    line: 12,
+                    character: 36,
+                },
+                cell: None,
+            },
+        },
+        Symbol {
+            name: String::from("path"),
+            kind: String::from("field"),
+            container_name: Some(String::from("AStar")),
+            qualified_name: String::from("AStar.path"),
+            decorators: Vec::new(),
+            documentation: None,
+            identifier_position: FilePosition {
+                path: String::from("AStar.java"),
+                position: Position {
+                    line: 13,
+                    character: 29,
+                },
+                cell: None,
+            },
+            range: FileRange {
+                path: String::from("AStar.java"),
+                start: Position {
+                    line: 13,
+                    character: 0,
+                },
+                end: Position {
+                    line: 13,
+                    character: 34,
+                },
+                cell: None,
+            },
+        },
+        Symbol {
+            name: String::from("maze"),
+            kind: String::from("field"),
+            container_name: Some(String::from("AStar")),
+            qualified_name: String::from("AStar.maze"),
+            decorators: Vec::new(),
+            documentation: None,
+            identifier_position: FilePosition {
+                path: String::from("AStar.java"),
+                position: Position {
+                    line: 14,
+                    character: 26,
+                },
+                cell: None,
+            },
+            range: FileRange {
+                path: String::from("AStar.java"),
+                start: Position {
+                    line: 14,
+                    character: 0,
+                },
+                end: Position {
+                    line: 14,
+                    character: 31,
+                },
+                cell: None,
+            },
+        },
+        Symbol {
+            name: String::from("now"),
+            kind: String::from("field"),
+            container_name: Some(String::from("AStar")),
+            qualified_name: String::from("AStar.now"),
+            decorators: Vec::new(),
+            documentation: None,
+            identifier_position: FilePosition {
+                path: String::from("AStar.java"),
+                position: Position {
+                    line: 15,
+                    character: 17,
+                },
+                cell: None,
+            },
+            range: FileRange {
+                path: String::from("AStar.java"),
+                start: Position {
+                    line: 15,
+                    character: 0,
+                },
+                end: Position {
+                    line: 15,
+                    character: 21,
+                },
+                cell: None,
+            },
+        },
+        Symbol {
+            name: String::from("xstart"),
+            kind: String::from("field"),
+            container_name: Some(String::from("AStar")),
+            qualified_name: String::from("AStar.xstart"),
+            decorators: Vec::new(),
+            documentation: None,
+            identifier_position: FilePosition {
+                path: String::from("AStar.java"),
+                position: Position {
+                    line: 16,
+                    character: 22,
+                },
+                cell: None,
+            },
+            range: FileRange {
+                path: String::from("AStar.java"),
+                start: Position {
+                    line: 16,
+                    character: 0,
+                },
+                end: Position {
+                    line: 16,
+                    character: 29,
+                },
+                cell: None,
+            },
+        },
+        Symbol {
+            name: String::from("ystart"),
+            kind: String::from("field"),
+            container_name: Some(String::from("AStar")),
+            qualified_name: String::from("AStar.ystart"),
+            decorators: Vec::new(),
+            documentation: None,
+            identifier_position: FilePosition {
+                path: String::from("AStar.java"),
+                position: Position {
+                    line: 17,
+                    character: 22,
+                },
+                cell: None,
+            },
+            range: FileRange {
+                path: String::from("AStar.java"),
+                start: Position {
+                    line: 17,
+                    character: 0,
+                },
+                end: Position {
+                    line: 17,
+                    character: 29,
+                },
+                cell: None,
+            },
+        },
+        Symbol {
+            name: String::from("xend"),
+            kind: String::from("field"),
+            container_name: Some(String::from("AStar")),
+            qualified_name: String::from("AStar.xend"),
+            decorators: Vec::new(),
+            documentation: None,
+            identifier_position: FilePosition {
+                path: String::from("AStar.java"),
+                position: Position {
+                    line: 18,
+                    character: 16,
+                },
+                cell: None,
+            },
+            range: FileRange {
+                path: String::from("AStar.java"),
+                start: Position {
+                    line: 18,
+                    character: 0,
+                },
+                end: Position {
+                    line: 18,
+                    character: 27,
+                },
+                cell: None,
+            },
+        },
+        Symbol {
+            name: String::from("yend"),
+            kind: String::from("field"),
+            container_name: Some(String::from("AStar")),
+            qualified_name: String::from("AStar.yend"),
+            decorators: Vec::new(),
+            documentation: None,
+            identifier_position: FilePosition {
+                path: String::from("AStar.java"),
+                position: Position {
+                    line: 18,
+                    character: 22,
+                },
+                cell: None,
+            },
+            range: FileRange {
+                path: String::from("AStar.java"),
+                start: Position {
+                    line: 18,
+                    character: 0,
+                },
+                end: Position {
+                    line: 18,
+                    character: 27,
+                },
+                cell: None,
+            },
+        },
+        Symbol {
+            name: String::from("diag"),
+            kind: String::from("field"),
+            container_name: Some(String::from("AStar")),
+            qualified_name: String::from("AStar.diag"),
+            decorators: Vec::new(),
+            documentation: None,
+            identifier_position: FilePosition {
+                path: String::from("AStar.java"),
+                position: Position {
+                    line: 19,
+                    character: 26,
+                },
+                cell: None,
+            },
+            range: FileRange {
+                path: String::from("AStar.java"),
+                start: Position {
+                    line: 19,
+                    character: 0,
+                },
+                end: Position {
+                    line: 19,
+                    character: 31,
+                },
+                cell: None,
             },
         },
         Symbol {
             name: String::from("findPathTo"),
             kind: String::from("method"),
+            container_name: Some(String::from("AStar")),
+            qualified_name: String::from("AStar.findPathTo"),
+            decorators: Vec::new(),
+            documentation: None,
             identifier_position: FilePosition {
                 path: String::from("AStar.java"),
                 position: Position {
                     line: 39,
                     character: 22,
                 },
+                cell: None,
             },
             range: FileRange {
                 path: String::from("AStar.java"),
@@ -139,17 +430,23 @@ fn test_server_integration_java() -> Result<(), Box<dyn std::error::Error>> {
                     line: 59,
                     character: 5,
                 },
+                cell: None,
             },
         },
         Symbol {
             name: String::from("addNeigborsToOpenList"),
             kind: String::from("method"),
+            container_name: Some(String::from("AStar")),
+            qualified_name: String::from("AStar.addNeigborsToOpenList"),
+            decorators: Vec::new(),
+            documentation: None,
             identifier_position: FilePosition {
                 path: String::from("AStar.java"),
                 position: Position {
                     line: 61,
                     character: 17,
                 },
+                cell: None,
             },
             range: FileRange {
                 path: String::from("AStar.java"),
@@ -161,17 +458,23 @@ fn test_server_integration_java() -> Result<(), Box<dyn std::error::Error>> {
                     line: 89,
                     character: 41,
                 },
+                cell: None,
             },
         },
         Symbol {
             name: String::from("distance"),
             kind: String::from("method"),
+            container_name: Some(String::from("AStar")),
+            qualified_name: String::from("AStar.distance"),
+            decorators: Vec::new(),
+            documentation: None,
             identifier_position: FilePosition {
                 path: String::from("AStar.java"),
                 position: Position {
                     line: 93,
                     character: 55,
                 },
+                cell: None,
             },
             range: FileRange {
                 path: String::from("AStar.java"),
@@ -183,17 +486,23 @@ fn test_server_integration_java() -> Result<(), Box<dyn std::error::Error>> {
                     line: 95,
                     character: 41,
                 },
+                cell: None,
             },
         },
         Symbol {
             name: String::from("main"),
             kind: String::from("method"),
+            container_name: None,
+            qualified_name: String::from("main"),
+            decorators: Vec::new(),
+            documentation: None,
             identifier_position: FilePosition {
                 path: String::from("AStar.java"),
                 position: Position {
                     line: 98,
                     character: 59,
                 },
+                cell: None,
             },
             range: FileRange {
                 path: String::from("AStar.java"),
@@ -205,17 +514,23 @@ fn test_server_integration_java() -> Result<(), Box<dyn std::error::Error>> {
                     line: 136,
                     character: 5,
                 },
+                cell: None,
             },
         },
         Symbol {
             name: String::from("findNeighborInList"),
             kind: String::from("method"),
+            container_name: None,
+            qualified_name: String::from("findNeighborInList"),
+            decorators: Vec::new(),
+            documentation: None,
             identifier_position: FilePosition {
                 path: String::from("AStar.java"),
                 position: Position {
                     line: 138,
                     character: 20,
                 },
+                cell: None,
             },
             range: FileRange {
                 path: String::from("AStar.java"),
@@ -227,6 +542,7 @@ fn test_server_integration_java() -> Result<(), Box<dyn std::error::Error>> {
                     line: 140,
                     character: 5,
                 },
+                cell: None,
             },
         },
     ];
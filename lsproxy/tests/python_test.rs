@@ -99,12 +99,17 @@ fn test_server_integration_python() -> Result<(), Box<dyn std::error::Error>> {
         Symbol {
             name: String::from("graph"),
             kind: String::from("variable"),
+            container_name: None,
+            qualified_name: String::from("main.graph"),
+            decorators: Vec::new(),
+            documentation: None,
             identifier_position: FilePosition {
                 path: String::from("main.py"),
                 position: Position {
                     line: 5,
                     character: 0,
                 },
+                cell: None,
             },
             range: FileRange {
                 path: String::from("main.py"),
@@ -116,17 +121,23 @@ fn test_server_integration_python() -> Result<(), Box<dyn std::error::Error>> {
                     line: 5,
                     character: 20,
                 },
+                cell: None,
             },
         },
         Symbol {
             name: String::from("result"),
             kind: String::from("variable"),
+            container_name: None,
+            qualified_name: String::from("main.result"),
+            decorators: Vec::new(),
+            documentation: None,
             identifier_position: FilePosition {
                 path: String::from("main.py"),
                 position: Position {
                     line: 6,
                     character: 0,
                 },
+                cell: None,
             },
             range: FileRange {
                 path: String::from("main.py"),
@@ -138,17 +149,23 @@ fn test_server_integration_python() -> Result<(), Box<dyn std::error::Error>> {
                     line: 6,
                     character: 51,
                 },
+                cell: None,
             },
         },
         Symbol {
             name: String::from("cost"),
             kind: String::from("variable"),
+            container_name: None,
+            qualified_name: String::from("main.cost"),
+            decorators: Vec::new(),
+            documentation: None,
             identifier_position: FilePosition {
                 path: String::from("main.py"),
                 position: Position {
                     line: 6,
                     character: 8,
                 },
+                cell: None,
             },
             range: FileRange {
                 path: String::from("main.py"),
@@ -160,6 +177,7 @@ fn test_server_integration_python() -> Result<(), Box<dyn std::error::Error>> {
                     line: 6,
                     character: 51,
                 },
+                cell: None,
             },
         },
     ];
@@ -62,7 +62,7 @@ install_python() {
        echo "Warning: EXTERNALLY-MANAGED file not found at $MANAGED_FILE"
    fi
 
-   pip3 install jedi-language-server ast-grep-cli
+   pip3 install jedi-language-server basedpyright
 }
 
 # Function to install Node.js
@@ -86,10 +86,32 @@ install_java() {
     echo 'export PATH="/opt/jdtls/bin:${PATH}"' >> /etc/profile.d/jdtls.sh
 }
 
+# Function to install .NET and csharp-ls
+install_dotnet() {
+    echo "Installing .NET and csharp-ls..."
+    curl -fsSL https://dot.net/v1/dotnet-install.sh -o /tmp/dotnet-install.sh
+    bash /tmp/dotnet-install.sh --channel 8.0 --install-dir /usr/share/dotnet
+    ln -sf /usr/share/dotnet/dotnet /usr/bin/dotnet
+    rm /tmp/dotnet-install.sh
+    dotnet tool install --tool-path /opt/csharp-ls csharp-ls
+
+    # Add csharp-ls to PATH
+    echo 'export DOTNET_ROOT=/usr/share/dotnet' >> /etc/profile.d/csharp-ls.sh
+    echo 'export PATH="/opt/csharp-ls:${PATH}"' >> /etc/profile.d/csharp-ls.sh
+}
+
+# Function to install Ruby and ruby-lsp
+install_ruby() {
+    echo "Installing Ruby and ruby-lsp..."
+    DEBIAN_FRONTEND=noninteractive apt-get install -y ruby-full
+    gem install ruby-lsp
+}
+
 # Function to install Node.js dependencies
 install_node_deps() {
     echo "Installing Node.js dependencies..."
-    npm install -g typescript-language-server typescript
+    npm install -g typescript-language-server typescript bash-language-server perlnavigator-server intelephense pyright \
+        @vue/typescript-plugin typescript-svelte-plugin svelte-language-server
 }
 
 # Function to install Rust tooling
@@ -111,20 +133,6 @@ install_lsproxy() {
     chmod +x /usr/local/bin/lsproxy
 }
 
-# Function to install ast_grep configuration
-install_ast_grep_config() {
-    local config_url="https://github.com/agentic-labs/lsproxy/releases/download/${LSPROXY_VERSION}/lsproxy-${LSPROXY_VERSION}-ast-grep-rules.tar.gz"
-    local dest_dir="/usr/src/ast_grep"
-
-    echo "Downloading ast_grep configuration and rules..."
-    mkdir -p "$dest_dir"
-    curl -L -o /tmp/ast_grep.tar.gz "$config_url"
-
-    echo "Extracting ast_grep configuration and rules..."
-    tar -xzf /tmp/ast_grep.tar.gz -C "$dest_dir" --no-same-owner
-    rm /tmp/ast_grep.tar.gz
-}
-
 # Function to clean up
 cleanup() {
     echo "Cleaning up..."
@@ -141,10 +149,11 @@ main() {
     install_python
     install_nodejs
     install_java
+    install_dotnet
+    install_ruby
     install_node_deps
     install_rust_tools
     install_lsproxy
-    install_ast_grep_config
     cleanup
     
     echo "LSProxy installation complete!"
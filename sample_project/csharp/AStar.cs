@@ -0,0 +1,86 @@
+namespace AStarSearch;
+
+public class EuclideanHeuristic : IHeuristic
+{
+    public double Estimate(int x, int y, int xend, int yend)
+    {
+        return Math.Sqrt(Math.Pow(x - xend, 2) + Math.Pow(y - yend, 2));
+    }
+}
+
+public class AStar
+{
+    private readonly List<Node> _open = new();
+    private readonly List<Node> _closed = new();
+    private readonly int[,] _maze;
+    private readonly bool _diag;
+    private readonly IHeuristic _heuristic;
+    private Node _now;
+
+    public AStar(int[,] maze, int xstart, int ystart, bool diag, IHeuristic heuristic)
+    {
+        _maze = maze;
+        _diag = diag;
+        _heuristic = heuristic;
+        _now = new Node(null, xstart, ystart, 0, 0);
+    }
+
+    public List<Node>? FindPathTo(int xend, int yend)
+    {
+        _closed.Add(_now);
+        AddNeighborsToOpenList(xend, yend);
+        while (_now.X != xend || _now.Y != yend)
+        {
+            if (_open.Count == 0)
+            {
+                return null;
+            }
+            _now = _open[0];
+            _open.RemoveAt(0);
+            _closed.Add(_now);
+            AddNeighborsToOpenList(xend, yend);
+        }
+
+        var path = new List<Node> { _now };
+        while (_now.Parent is not null)
+        {
+            _now = _now.Parent;
+            path.Insert(0, _now);
+        }
+        return path;
+    }
+
+    private void AddNeighborsToOpenList(int xend, int yend)
+    {
+        for (int x = -1; x <= 1; x++)
+        {
+            for (int y = -1; y <= 1; y++)
+            {
+                if (!_diag && x != 0 && y != 0)
+                {
+                    continue;
+                }
+                int nx = _now.X + x;
+                int ny = _now.Y + y;
+                if ((x == 0 && y == 0)
+                    || nx < 0 || nx >= _maze.GetLength(1)
+                    || ny < 0 || ny >= _maze.GetLength(0)
+                    || _maze[ny, nx] == -1)
+                {
+                    continue;
+                }
+                var node = new Node(_now, nx, ny, _now.G + 1 + _maze[ny, nx], _heuristic.Estimate(nx, ny, xend, yend));
+                if (!FindNeighborInList(_open, node) && !FindNeighborInList(_closed, node))
+                {
+                    _open.Add(node);
+                }
+            }
+        }
+        _open.Sort();
+    }
+
+    private static bool FindNeighborInList(List<Node> list, Node node)
+    {
+        return list.Exists(n => n.X == node.X && n.Y == node.Y);
+    }
+}
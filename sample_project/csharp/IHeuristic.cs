@@ -0,0 +1,6 @@
+namespace AStarSearch;
+
+public interface IHeuristic
+{
+    double Estimate(int x, int y, int xend, int yend);
+}
@@ -0,0 +1,28 @@
+namespace AStarSearch;
+
+public class Node : IComparable<Node>
+{
+    public Node? Parent { get; set; }
+    public int X { get; }
+    public int Y { get; }
+    public double G { get; set; }
+    public double H { get; set; }
+
+    public Node(Node? parent, int x, int y, double g, double h)
+    {
+        Parent = parent;
+        X = x;
+        Y = y;
+        G = g;
+        H = h;
+    }
+
+    public int CompareTo(Node? other)
+    {
+        if (other is null)
+        {
+            return 1;
+        }
+        return (G + H).CompareTo(other.G + other.H);
+    }
+}
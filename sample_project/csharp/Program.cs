@@ -0,0 +1,26 @@
+using AStarSearch;
+
+// -1 = blocked
+// 0+ = additional movement cost
+int[,] maze = {
+    {  0,  0,  0,  0,  0,  0,  0,  0},
+    {  0,  0,  0,  0,  0,  0,  0,  0},
+    {  0,  0,  0,100,100,100,  0,  0},
+    {  0,  0,  0,  0,  0,100,  0,  0},
+    {  0,  0,100,  0,  0,100,  0,  0},
+    {  0,  0,100,  0,  0,100,  0,  0},
+    {  0,  0,100,100,100,100,  0,  0},
+    {  0,  0,  0,  0,  0,  0,  0,  0},
+};
+
+var astar = new AStar(maze, 0, 0, true, new EuclideanHeuristic());
+var path = astar.FindPathTo(7, 7);
+if (path is not null)
+{
+    foreach (var n in path)
+    {
+        Console.Write($"[{n.X}, {n.Y}] ");
+        maze[n.Y, n.X] = -1;
+    }
+    Console.WriteLine($"\nTotal cost: {path[^1].G:F2}");
+}
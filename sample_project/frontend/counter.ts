@@ -0,0 +1,7 @@
+export function increment(count: number): number {
+    return count + 1;
+}
+
+export function reset(): number {
+    return 0;
+}
@@ -0,0 +1,28 @@
+<?php
+
+require_once __DIR__ . '/src/AStar.php';
+
+use AStarSearch\AStar;
+use AStarSearch\EuclideanHeuristic;
+
+// -1 = blocked
+// 0+ = additional movement cost
+$maze = [
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 100, 100, 100, 0, 0],
+    [0, 0, 0, 0, 0, 100, 0, 0],
+    [0, 0, 100, 0, 0, 100, 0, 0],
+    [0, 0, 100, 0, 0, 100, 0, 0],
+    [0, 0, 100, 100, 100, 100, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+];
+
+$astar = new AStar($maze, true, new EuclideanHeuristic());
+$path = $astar->findPathTo(7, 7);
+if ($path !== null) {
+    foreach ($path as $node) {
+        echo "[{$node->x}, {$node->y}] ";
+    }
+    printf("\nTotal cost: %.2f\n", end($path)->g);
+}
@@ -0,0 +1,88 @@
+<?php
+
+namespace AStarSearch;
+
+require_once __DIR__ . '/Heuristic.php';
+require_once __DIR__ . '/Node.php';
+
+class EuclideanHeuristic implements Heuristic
+{
+    public function estimate(int $x, int $y, int $xend, int $yend): float
+    {
+        return sqrt(($x - $xend) ** 2 + ($y - $yend) ** 2);
+    }
+}
+
+class AStar
+{
+    /** @var Node[] */
+    private array $open = [];
+    /** @var Node[] */
+    private array $closed = [];
+
+    public function __construct(
+        private array $maze,
+        private bool $diag,
+        private Heuristic $heuristic
+    ) {
+    }
+
+    /**
+     * @return Node[]|null
+     */
+    public function findPathTo(int $xend, int $yend): ?array
+    {
+        $now = new Node(null, 0, 0, 0, 0);
+        $this->closed[] = $now;
+        $this->addNeighborsToOpenList($now, $xend, $yend);
+        while ($now->x !== $xend || $now->y !== $yend) {
+            if (count($this->open) === 0) {
+                return null;
+            }
+            $now = array_shift($this->open);
+            $this->closed[] = $now;
+            $this->addNeighborsToOpenList($now, $xend, $yend);
+        }
+
+        $path = [$now];
+        while ($now->parent !== null) {
+            $now = $now->parent;
+            array_unshift($path, $now);
+        }
+        return $path;
+    }
+
+    private function addNeighborsToOpenList(Node $now, int $xend, int $yend): void
+    {
+        for ($dx = -1; $dx <= 1; $dx++) {
+            for ($dy = -1; $dy <= 1; $dy++) {
+                if (($dx === 0 && $dy === 0) || (!$this->diag && $dx !== 0 && $dy !== 0)) {
+                    continue;
+                }
+                $x = $now->x + $dx;
+                $y = $now->y + $dy;
+                if ($y < 0 || $y >= count($this->maze) || $x < 0 || $x >= count($this->maze[0])) {
+                    continue;
+                }
+                if ($this->maze[$y][$x] === -1) {
+                    continue;
+                }
+                $node = new Node($now, $x, $y, $now->g + 1 + $this->maze[$y][$x], $this->heuristic->estimate($x, $y, $xend, $yend));
+                if (!$this->inList($this->open, $node) && !$this->inList($this->closed, $node)) {
+                    $this->open[] = $node;
+                }
+            }
+        }
+        usort($this->open, fn (Node $a, Node $b) => $a->compareTo($b));
+    }
+
+    private function inList(array $list, Node $node): bool
+    {
+        foreach ($list as $candidate) {
+            if (same_position($candidate, $node)) {
+                return true;
+            }
+        }
+        return false;
+    }
+}
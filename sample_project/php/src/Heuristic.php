@@ -0,0 +1,8 @@
+<?php
+
+namespace AStarSearch;
+
+interface Heuristic
+{
+    public function estimate(int $x, int $y, int $xend, int $yend): float;
+}
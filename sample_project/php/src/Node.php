@@ -0,0 +1,35 @@
+<?php
+
+namespace AStarSearch;
+
+trait ComparesCost
+{
+    public function compareTo(Node $other): int
+    {
+        return $this->cost() <=> $other->cost();
+    }
+}
+
+class Node
+{
+    use ComparesCost;
+
+    public function __construct(
+        public ?Node $parent,
+        public int $x,
+        public int $y,
+        public float $g,
+        public float $h
+    ) {
+    }
+
+    public function cost(): float
+    {
+        return $this->g + $this->h;
+    }
+}
+
+function same_position(Node $a, Node $b): bool
+{
+    return $a->x === $b->x && $a->y === $b->y;
+}
@@ -0,0 +1,67 @@
+# frozen_string_literal: true
+
+require_relative "node"
+
+module AStarSearch
+  class AStar
+    def initialize(maze, diag: true)
+      @maze = maze
+      @diag = diag
+    end
+
+    def find_path(xend, yend)
+      open = [Node.origin]
+      closed = []
+      until open.empty?
+        current = open.min
+        return reconstruct(current) if current.x == xend && current.y == yend
+
+        open.delete(current)
+        closed << current
+        neighbours(current, xend, yend).each do |node|
+          next if contains?(closed, node) || contains?(open, node)
+
+          open << node
+        end
+      end
+      nil
+    end
+
+    private
+
+    def neighbours(current, xend, yend)
+      (-1..1).to_a.product((-1..1).to_a).filter_map do |dx, dy|
+        next if dx.zero? && dy.zero?
+        next if !@diag && !dx.zero? && !dy.zero?
+
+        x = current.x + dx
+        y = current.y + dy
+        next unless walkable?(x, y)
+
+        cost = dx.zero? || dy.zero? ? 1 : DIAGONAL_COST
+        Node.new(current, x, y, current.g + cost + @maze[y][x], distance(x, y, xend, yend))
+      end
+    end
+
+    def walkable?(x, y)
+      y.between?(0, @maze.length - 1) && x.between?(0, @maze[0].length - 1) && @maze[y][x] != -1
+    end
+
+    def distance(x, y, xend, yend)
+      Math.sqrt(((x - xend)**2) + ((y - yend)**2))
+    end
+
+    def contains?(list, node)
+      list.any? { |n| n.x == node.x && n.y == node.y }
+    end
+
+    def reconstruct(node)
+      path = []
+      while node
+        path.unshift(node)
+        node = node.parent
+      end
+      path
+    end
+  end
+end
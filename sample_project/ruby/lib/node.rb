@@ -0,0 +1,32 @@
+# frozen_string_literal: true
+
+module AStarSearch
+  DIAGONAL_COST = 1.4
+
+  class Node
+    include Comparable
+
+    attr_accessor :parent, :g, :h
+    attr_reader :x, :y
+
+    def initialize(parent, x, y, g, h)
+      @parent = parent
+      @x = x
+      @y = y
+      @g = g
+      @h = h
+    end
+
+    def f
+      g + h
+    end
+
+    def <=>(other)
+      f <=> other.f
+    end
+
+    def self.origin
+      new(nil, 0, 0, 0, 0)
+    end
+  end
+end
@@ -0,0 +1,18 @@
+# frozen_string_literal: true
+
+require_relative "lib/astar"
+
+MAZE = [
+  [0, 0, 0, 0, 0, 0, 0, 0],
+  [0, 0, 0, 0, 0, 0, 0, 0],
+  [0, 0, 0, 100, 100, 100, 0, 0],
+  [0, 0, 0, 0, 0, 100, 0, 0],
+  [0, 0, 100, 0, 0, 100, 0, 0],
+  [0, 0, 100, 0, 0, 100, 0, 0],
+  [0, 0, 100, 100, 100, 100, 0, 0],
+  [0, 0, 0, 0, 0, 0, 0, 0]
+].freeze
+
+path = AStarSearch::AStar.new(MAZE).find_path(7, 7)
+puts path.map { |n| "[#{n.x}, #{n.y}]" }.join(" ")
+puts "Total cost: #{format('%.2f', path.last.g)}"